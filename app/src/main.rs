@@ -89,3 +89,23 @@ impl eframe::epi::App for MyApp {
         ctx.request_repaint();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use eframe_stripped::testing::TestHarness;
+
+    use super::MyApp;
+
+    #[test]
+    fn closes_after_two_ticks() {
+        let mut harness = TestHarness::new(Box::new(|_| Ok(Box::new(MyApp::default())))).unwrap();
+
+        for _ in 0..2 {
+            let output = harness.step();
+            assert!(!TestHarness::close_requested(&output));
+        }
+
+        let output = harness.step();
+        assert!(TestHarness::close_requested(&output));
+    }
+}
@@ -1,4 +1,5 @@
 #!/usr/bin/env bash
 
-RUST_LOG=debug cargo run -q -- main &> out_main
-RUST_LOG=debug cargo run -q -- spawn &> out_spawn
+RUST_LOG=debug EFRAME_EVENT_TRACE=trace_main.jsonl cargo run -q -- main &> out_main
+RUST_LOG=debug EFRAME_EVENT_TRACE=trace_spawn.jsonl cargo run -q -- spawn &> out_spawn
+cargo run -q -p trace_diff -- trace_main.jsonl trace_spawn.jsonl "$@"
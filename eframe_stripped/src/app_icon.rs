@@ -8,33 +8,106 @@ use egui::IconData;
 
 pub struct AppTitleIconSetter {
     title: String,
-    icon_data: Option<Arc<IconData>>,
+
+    /// The icon in one or more sizes, see [`crate::epi::set_app_icons`].
+    icons: Vec<Arc<IconData>>,
+
+    /// What [`Self::icons`] go back to when no icon is given.
+    default_icons: Vec<Arc<IconData>>,
+
+    /// [`egui::ViewportBuilder::app_id`], which Wayland uses to find the icon.
+    app_id: Option<String>,
+
+    /// See [`crate::NativeOptions::install_desktop_entry`].
+    install_desktop_entry: bool,
+
     status: AppIconStatus,
 }
 
 impl AppTitleIconSetter {
-    pub fn new(title: String, mut icon_data: Option<Arc<IconData>>) -> Self {
-        if let Some(icon) = &icon_data {
-            if **icon == IconData::default() {
-                icon_data = None;
-            }
-        }
-
+    pub fn new(
+        title: String,
+        icon_data: Option<Arc<IconData>>,
+        app_id: Option<String>,
+        install_desktop_entry: bool,
+    ) -> Self {
+        let icons = without_empty(icon_data.into_iter().collect());
         Self {
             title,
-            icon_data,
+            icons: icons.clone(),
+            default_icons: icons,
+            app_id,
+            install_desktop_entry,
             status: AppIconStatus::NotSetTryAgain,
         }
     }
 
-    /// Call once per frame; we will set the icon when we can.
-    pub fn update(&mut self) {
+    /// Call once per frame with the root window and its current title and icons.
+    ///
+    /// We will set them when we can, and again whenever they change.
+    /// `None` keeps the title we have, and no icons go back to the icon we were created with.
+    pub fn update(
+        &mut self,
+        window: &winit::window::Window,
+        title: Option<&str>,
+        icons: Vec<Arc<IconData>>,
+    ) {
+        if let Some(title) = title
+            && title != self.title
+        {
+            self.title = title.to_owned();
+            // Only the Mac menu bar shows it. Elsewhere this would just set the icon again,
+            // which on Wayland means writing the `.desktop` entry and icons again.
+            if cfg!(target_os = "macos") {
+                self.status = AppIconStatus::NotSetTryAgain;
+            }
+        }
+        let icons = if icons.is_empty() {
+            self.default_icons.clone()
+        } else {
+            without_empty(icons)
+        };
+        if !same_icons(&icons, &self.icons) {
+            self.icons = icons;
+            self.status = AppIconStatus::NotSetTryAgain;
+        }
+
         if self.status == AppIconStatus::NotSetTryAgain {
-            self.status = set_title_and_icon(&self.title, self.icon_data.as_deref());
+            self.status = set_title_and_icon(
+                &self.title,
+                &self.icons,
+                self.app_id.as_deref(),
+                self.install_desktop_entry,
+                window,
+            );
         }
     }
 }
 
+/// [`IconData::default`] means "no icon", see [`egui::ViewportBuilder::icon`].
+fn without_empty(mut icons: Vec<Arc<IconData>>) -> Vec<Arc<IconData>> {
+    icons.retain(|icon| **icon != IconData::default());
+    icons
+}
+
+/// Compares pointers first, since the icons are usually the same [`Arc`]s every frame.
+fn same_icons(a: &[Arc<IconData>], b: &[Arc<IconData>]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Arc::ptr_eq(a, b) || a == b)
+}
+
+/// The smallest icon that is at least `size` pixels wide and high, or else the largest one,
+/// so we scale down rather than up.
+#[allow(dead_code, clippy::allow_attributes)] // Not used on Android and iOS
+fn icon_for_size(icons: &[Arc<IconData>], size: u32) -> Option<&IconData> {
+    let icon_size = |icon: &&Arc<IconData>| icon.width.min(icon.height);
+    icons
+        .iter()
+        .filter(|icon| icon_size(icon) >= size)
+        .min_by_key(icon_size)
+        .or_else(|| icons.iter().max_by_key(icon_size))
+        .map(|icon| &**icon)
+}
+
 /// In which state the app icon is (as far as we know).
 #[derive(PartialEq, Eq)]
 enum AppIconStatus {
@@ -47,7 +120,7 @@ enum AppIconStatus {
     NotSetTryAgain,
 
     /// We successfully set the icon and it should be visible now.
-    #[allow(dead_code, clippy::allow_attributes)] // Not used on Linux
+    #[allow(dead_code, clippy::allow_attributes)] // Not used on Android and iOS
     Set,
 }
 
@@ -58,27 +131,89 @@ enum AppIconStatus {
 ///
 /// Since window creation can be lazy, call this every frame until it's either successfully or gave up.
 /// (See [`AppIconStatus`])
-fn set_title_and_icon(_title: &str, _icon_data: Option<&IconData>) -> AppIconStatus {
+fn set_title_and_icon(
+    _title: &str,
+    _icons: &[Arc<IconData>],
+    _app_id: Option<&str>,
+    _install_desktop_entry: bool,
+    _window: &winit::window::Window,
+) -> AppIconStatus {
     profiling::function_scope!();
 
     #[cfg(target_os = "windows")]
     {
-        if let Some(icon_data) = _icon_data {
-            return set_app_icon_windows(icon_data);
+        if !_icons.is_empty() {
+            return set_app_icon_windows(_icons);
         }
     }
 
+    // The dock shows the icon large, and scales the largest one down well.
     #[cfg(target_os = "macos")]
-    return set_title_and_icon_mac(_title, _icon_data);
+    return set_title_and_icon_mac(_title, icon_for_size(_icons, u32::MAX));
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+    ))]
+    {
+        if !_icons.is_empty() {
+            return linux::set_app_icon(_title, _icons, _app_id, _install_desktop_entry, _window);
+        }
+    }
 
     #[allow(unreachable_code, clippy::allow_attributes)]
     AppIconStatus::NotSetIgnored
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icon(width: u32, height: u32) -> Arc<IconData> {
+        Arc::new(IconData {
+            rgba: vec![255; (width * height * 4) as usize],
+            width,
+            height,
+        })
+    }
+
+    #[test]
+    fn icon_for_size_prefers_scaling_down() {
+        let icons = [icon(16, 16), icon(64, 64), icon(32, 32)];
+        let size = |size| icon_for_size(&icons, size).map(|icon| icon.width);
+        assert_eq!(size(16), Some(16));
+        assert_eq!(size(17), Some(32));
+        assert_eq!(size(48), Some(64));
+        assert_eq!(size(256), Some(64));
+        assert_eq!(size(0), Some(16));
+        assert_eq!(icon_for_size(&[], 16), None);
+
+        // The shorter side counts.
+        let icons = [icon(64, 16), icon(32, 32)];
+        assert_eq!(icon_for_size(&icons, 24).map(|icon| icon.width), Some(32));
+    }
+
+    #[test]
+    fn without_empty_drops_default_icons() {
+        let icons = vec![Arc::new(IconData::default()), icon(16, 16), Arc::default()];
+        assert_eq!(without_empty(icons), [icon(16, 16)]);
+        assert_eq!(without_empty(Vec::new()), []);
+    }
+
+    #[test]
+    fn same_icons_compares_contents() {
+        let small = [icon(16, 16)];
+        assert!(same_icons(&small, &small));
+        assert!(same_icons(&small, &[icon(16, 16)]));
+        assert!(!same_icons(&small, &[icon(32, 32)]));
+        assert!(!same_icons(&small, &[small[0].clone(), small[0].clone()]));
+    }
+}
+
 /// Set icon for Windows applications.
 #[cfg(target_os = "windows")]
 #[expect(unsafe_code)]
-fn set_app_icon_windows(icon_data: &IconData) -> AppIconStatus {
+fn set_app_icon_windows(icons: &[std::sync::Arc<IconData>]) -> AppIconStatus {
     use crate::icon_data::IconDataExt as _;
     use winapi::um::winuser;
 
@@ -102,12 +237,16 @@ fn set_app_icon_windows(icon_data: &IconData) -> AppIconStatus {
         unscaled_image: &image::RgbaImage,
         target_size: i32,
     ) -> winapi::shared::windef::HICON {
-        let image_scaled = image::imageops::resize(
-            unscaled_image,
-            target_size as _,
-            target_size as _,
-            image::imageops::Lanczos3,
-        );
+        let image_scaled = if unscaled_image.dimensions() == (target_size as _, target_size as _) {
+            unscaled_image.clone()
+        } else {
+            image::imageops::resize(
+                unscaled_image,
+                target_size as _,
+                target_size as _,
+                image::imageops::Lanczos3,
+            )
+        };
 
         // Creating transparent icons with WinApi is a huge mess.
         // We'd need to go through CreateIconIndirect's ICONINFO struct which then
@@ -139,11 +278,15 @@ fn set_app_icon_windows(icon_data: &IconData) -> AppIconStatus {
         }
     }
 
-    let unscaled_image = match icon_data.to_image() {
-        Ok(unscaled_image) => unscaled_image,
-        Err(err) => {
-            log::warn!("Invalid icon: {err}");
-            return AppIconStatus::NotSetIgnored;
+    // Of the icons we have, the one closest to the size Windows wants.
+    let unscaled_image_for_size = |target_size: i32| {
+        let icon_data = super::icon_for_size(icons, target_size as u32)?;
+        match icon_data.to_image() {
+            Ok(unscaled_image) => Some(unscaled_image),
+            Err(err) => {
+                log::warn!("Invalid icon: {err}");
+                None
+            }
         }
     };
 
@@ -156,6 +299,9 @@ fn set_app_icon_windows(icon_data: &IconData) -> AppIconStatus {
     {
         // SAFETY: WinAPI getter function with no known side effects.
         let icon_size_big = unsafe { winuser::GetSystemMetrics(winuser::SM_CXICON) };
+        let Some(unscaled_image) = unscaled_image_for_size(icon_size_big) else {
+            return AppIconStatus::NotSetIgnored;
+        };
         let icon_big = create_hicon_with_scale(&unscaled_image, icon_size_big);
         if icon_big.is_null() {
             log::warn!("Failed to create HICON (for big icon) from embedded png data.");
@@ -175,6 +321,9 @@ fn set_app_icon_windows(icon_data: &IconData) -> AppIconStatus {
     {
         // SAFETY: WinAPI getter function with no known side effects.
         let icon_size_small = unsafe { winuser::GetSystemMetrics(winuser::SM_CXSMICON) };
+        let Some(unscaled_image) = unscaled_image_for_size(icon_size_small) else {
+            return AppIconStatus::NotSetIgnored;
+        };
         let icon_small = create_hicon_with_scale(&unscaled_image, icon_size_small);
         if icon_small.is_null() {
             log::warn!("Failed to create HICON (for small icon) from embedded png data.");
@@ -260,3 +409,393 @@ fn set_title_and_icon_mac(title: &str, icon_data: Option<&IconData>) -> AppIconS
 
     AppIconStatus::Set
 }
+
+/// Set the icon on X11 and Wayland.
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+))]
+mod linux {
+    use std::{
+        collections::BTreeSet,
+        path::{Path, PathBuf},
+        sync::Arc,
+    };
+
+    use egui::IconData;
+    use raw_window_handle::{HasWindowHandle as _, RawWindowHandle};
+
+    use super::{AppIconStatus, icon_for_size};
+
+    /// Marks the `.desktop` entries we wrote, so we never overwrite one we didn't.
+    const GENERATED_KEY: &str = "X-Eframe-Generated";
+
+    /// The sizes in the hicolor theme; icons in directories for other sizes are ignored.
+    const HICOLOR_SIZES: [u32; 10] = [16, 22, 24, 32, 48, 64, 96, 128, 256, 512];
+
+    pub fn set_app_icon(
+        title: &str,
+        icons: &[Arc<IconData>],
+        app_id: Option<&str>,
+        install: bool,
+        window: &winit::window::Window,
+    ) -> AppIconStatus {
+        let Ok(handle) = window.window_handle() else {
+            return AppIconStatus::NotSetTryAgain;
+        };
+        match handle.as_raw() {
+            RawWindowHandle::Xlib(_) | RawWindowHandle::Xcb(_) => {
+                // winit only sets one size, so give the largest and let the taskbar scale it down.
+                match icon_for_size(icons, u32::MAX) {
+                    Some(icon_data) => set_app_icon_x11(icon_data, window),
+                    None => AppIconStatus::NotSetIgnored,
+                }
+            }
+            RawWindowHandle::Wayland(_) => {
+                let Some(app_id) = app_id else {
+                    log::debug!(
+                        "Wayland finds app icons through the app id, but ViewportBuilder::app_id isn't set"
+                    );
+                    return AppIconStatus::NotSetIgnored;
+                };
+                if !is_desktop_file_id(app_id) {
+                    log::warn!(
+                        "The app id {app_id:?} can't name a .desktop entry, so Wayland can't find the app icon"
+                    );
+                    return AppIconStatus::NotSetIgnored;
+                }
+                match install_desktop_entry(title, icons, app_id, install) {
+                    Ok(status) => status,
+                    Err(err) => {
+                        log::warn!("Failed to install the app icon for {app_id:?}: {err}");
+                        AppIconStatus::NotSetIgnored
+                    }
+                }
+            }
+            _ => AppIconStatus::NotSetIgnored,
+        }
+    }
+
+    /// Set `_NET_WM_ICON` on the window, which taskbars and window switchers use.
+    fn set_app_icon_x11(icon_data: &IconData, window: &winit::window::Window) -> AppIconStatus {
+        match winit::window::Icon::from_rgba(
+            icon_data.rgba.clone(),
+            icon_data.width,
+            icon_data.height,
+        ) {
+            Ok(icon) => {
+                window.set_window_icon(Some(icon));
+                AppIconStatus::Set
+            }
+            Err(err) => {
+                log::warn!("Invalid icon: {err}");
+                AppIconStatus::NotSetIgnored
+            }
+        }
+    }
+
+    /// Wayland has no way to set the icon of a window (the xdg-toplevel-icon protocol isn't
+    /// supported by winit). Instead compositors show the icon of the `.desktop` entry named like the app id,
+    /// so we install one, with the icons in the hicolor theme, in `$XDG_DATA_HOME`.
+    /// Each icon goes into the directory for its size, so the compositor can pick one.
+    ///
+    /// Entries that weren't written by us, e.g. from a package, are left alone.
+    /// Without `install`, only existing entries are used.
+    fn install_desktop_entry(
+        title: &str,
+        icons: &[Arc<IconData>],
+        app_id: &str,
+        install: bool,
+    ) -> std::io::Result<AppIconStatus> {
+        profiling::function_scope!();
+
+        let file_name = format!("{app_id}.desktop");
+        let Some(data_home) = data_home() else {
+            log::debug!(
+                "Neither XDG_DATA_HOME nor HOME are set, so there is nowhere to install the app icon"
+            );
+            return Ok(AppIconStatus::NotSetIgnored);
+        };
+        if let Some(installed) = data_dirs()
+            .into_iter()
+            .map(|dir| dir.join("applications").join(&file_name))
+            .find(|path| path.exists())
+        {
+            log::debug!("Using the app icon from {}", installed.display());
+            return Ok(AppIconStatus::Set);
+        }
+
+        let entry_path = data_home.join("applications").join(&file_name);
+        let existing = std::fs::read_to_string(&entry_path).ok();
+        if let Some(existing) = &existing
+            && !existing.lines().any(|line| line.starts_with(GENERATED_KEY))
+        {
+            log::debug!("Using the app icon from {}", entry_path.display());
+            return Ok(AppIconStatus::Set);
+        }
+        if !install {
+            log::debug!(
+                "There is no .desktop entry for {app_id:?}, and NativeOptions::install_desktop_entry is off"
+            );
+            return Ok(AppIconStatus::NotSetIgnored);
+        }
+
+        let sizes: BTreeSet<u32> = icons
+            .iter()
+            .map(|icon| {
+                HICOLOR_SIZES
+                    .into_iter()
+                    .find(|&size| icon.width.max(icon.height) <= size)
+                    .unwrap_or(512)
+            })
+            .collect();
+        for size in sizes {
+            let Some(icon_data) = icon_for_size(icons, size) else {
+                continue;
+            };
+            let icon_path = data_home
+                .join("icons/hicolor")
+                .join(format!("{size}x{size}/apps/{app_id}.png"));
+            write_if_changed(&icon_path, &icon_png(icon_data, size)?)?;
+        }
+
+        let entry = desktop_entry(title, app_id, &std::env::current_exe()?);
+        if write_if_changed(&entry_path, entry.as_bytes())? {
+            log::debug!("Installed {}", entry_path.display());
+        }
+        Ok(AppIconStatus::Set)
+    }
+
+    /// Desktop file ids are made of ASCII letters, digits, `_`, `-` and `.`,
+    /// which also keeps `app_id` from reaching outside the directories we put files in.
+    fn is_desktop_file_id(app_id: &str) -> bool {
+        !app_id.is_empty()
+            && app_id.len() <= 255
+            && !app_id.starts_with('.')
+            && app_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c))
+    }
+
+    fn desktop_entry(title: &str, app_id: &str, exe: &Path) -> String {
+        // Quoted and escaped as the spec says, so paths with spaces work.
+        let mut exec = String::new();
+        for c in exe.to_string_lossy().chars() {
+            if matches!(c, '"' | '`' | '$' | '\\') {
+                exec.push('\\');
+            }
+            exec.push(c);
+        }
+        // Backslashes are escaped once more, since `Exec` is also a string value,
+        // and `%` starts a field code.
+        let exec = exec.replace('\\', "\\\\").replace('%', "%%");
+        let name = title.replace(['\n', '\r'], " ");
+        format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name={name}\n\
+             Exec=\"{exec}\"\n\
+             Icon={app_id}\n\
+             StartupWMClass={app_id}\n\
+             NoDisplay=true\n\
+             {GENERATED_KEY}=true\n"
+        )
+    }
+
+    fn icon_png(icon_data: &IconData, size: u32) -> std::io::Result<Vec<u8>> {
+        let invalid = |err: &dyn std::fmt::Display| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid icon: {err}"),
+            )
+        };
+        let image =
+            image::RgbaImage::from_raw(icon_data.width, icon_data.height, icon_data.rgba.clone())
+                .ok_or_else(|| invalid(&"the size doesn't match the pixels"))?;
+        let image = if image.dimensions() == (size, size) {
+            image
+        } else {
+            // Icons are square, so scale to fit and center the rest on a transparent background.
+            let scale = size as f32 / image.width().max(image.height()) as f32;
+            let width = ((image.width() as f32 * scale).round() as u32).clamp(1, size);
+            let height = ((image.height() as f32 * scale).round() as u32).clamp(1, size);
+            let scaled = image::imageops::resize(&image, width, height, image::imageops::Lanczos3);
+            let mut square = image::RgbaImage::new(size, size);
+            image::imageops::overlay(
+                &mut square,
+                &scaled,
+                ((size - width) / 2).into(),
+                ((size - height) / 2).into(),
+            );
+            square
+        };
+        let mut png = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .map_err(|err| invalid(&err))?;
+        Ok(png)
+    }
+
+    /// Returns `false` if `path` already had `contents`.
+    fn write_if_changed(path: &Path, contents: &[u8]) -> std::io::Result<bool> {
+        if std::fs::read(path).is_ok_and(|existing| existing == contents) {
+            return Ok(false);
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, contents)?;
+        Ok(true)
+    }
+
+    fn data_home() -> Option<PathBuf> {
+        data_home_from(std::env::var_os("XDG_DATA_HOME"), std::env::var_os("HOME"))
+    }
+
+    /// Relative paths are invalid in the XDG variables, and ignored.
+    fn data_home_from(
+        xdg_data_home: Option<std::ffi::OsString>,
+        home: Option<std::ffi::OsString>,
+    ) -> Option<PathBuf> {
+        xdg_data_home
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| {
+                Some(PathBuf::from(home?))
+                    .filter(|home| home.is_absolute())
+                    .map(|home| home.join(".local/share"))
+            })
+    }
+
+    /// The system-wide data dirs, where packages install their `.desktop` entries.
+    fn data_dirs() -> Vec<PathBuf> {
+        data_dirs_from(std::env::var("XDG_DATA_DIRS").ok().as_deref())
+    }
+
+    fn data_dirs_from(xdg_data_dirs: Option<&str>) -> Vec<PathBuf> {
+        let dirs: Vec<PathBuf> = xdg_data_dirs
+            .unwrap_or_default()
+            .split(':')
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .collect();
+        if dirs.is_empty() {
+            vec!["/usr/local/share".into(), "/usr/share".into()]
+        } else {
+            dirs
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn desktop_file_ids() {
+            assert!(is_desktop_file_id("org.example.App"));
+            assert!(is_desktop_file_id("my_app-2"));
+            for invalid in ["", ".", "..", ".hidden", "../x", "a/b", "a b", "ä", "a%b"] {
+                assert!(!is_desktop_file_id(invalid), "{invalid:?}");
+            }
+        }
+
+        #[test]
+        fn desktop_entry_escapes_exec() {
+            let entry = desktop_entry(
+                "My\nApp",
+                "org.example.App",
+                Path::new(r#"/opt/my app/50%/"quoted" $x\bin"#),
+            );
+            assert_eq!(
+                entry,
+                format!(
+                    "[Desktop Entry]\n\
+                     Type=Application\n\
+                     Name=My App\n\
+                     Exec=\"/opt/my app/50%%/\\\\\"quoted\\\\\" \\\\$x\\\\\\\\bin\"\n\
+                     Icon=org.example.App\n\
+                     StartupWMClass=org.example.App\n\
+                     NoDisplay=true\n\
+                     {GENERATED_KEY}=true\n"
+                )
+            );
+        }
+
+        fn decode(png: &[u8]) -> image::RgbaImage {
+            image::load_from_memory(png).unwrap().into_rgba8()
+        }
+
+        #[test]
+        fn icon_png_pads_to_a_square() {
+            let red = [255, 0, 0, 255];
+            let icon = IconData {
+                rgba: red.repeat(4 * 2),
+                width: 4,
+                height: 2,
+            };
+
+            let png = decode(&icon_png(&icon, 8).unwrap());
+            assert_eq!(png.dimensions(), (8, 8));
+            assert_eq!(png.get_pixel(0, 0).0, [0, 0, 0, 0]);
+            assert_eq!(png.get_pixel(0, 7).0, [0, 0, 0, 0]);
+            assert_eq!(png.get_pixel(4, 4).0, red);
+
+            let square = IconData {
+                rgba: red.repeat(4 * 4),
+                width: 4,
+                height: 4,
+            };
+            assert_eq!(
+                decode(&icon_png(&square, 4).unwrap()).into_raw(),
+                square.rgba
+            );
+
+            let broken = IconData {
+                rgba: vec![0; 3],
+                width: 4,
+                height: 4,
+            };
+            let err = icon_png(&broken, 4).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+
+        #[test]
+        fn write_if_changed_only_writes_changes() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("icons/hicolor/16x16/apps/app.png");
+            assert!(write_if_changed(&path, b"one").unwrap());
+            assert!(!write_if_changed(&path, b"one").unwrap());
+            assert!(write_if_changed(&path, b"two").unwrap());
+            assert_eq!(std::fs::read(&path).unwrap(), b"two");
+        }
+
+        #[test]
+        fn data_dirs_skip_relative_paths() {
+            let defaults = [
+                PathBuf::from("/usr/local/share"),
+                PathBuf::from("/usr/share"),
+            ];
+            assert_eq!(data_dirs_from(None), defaults);
+            assert_eq!(data_dirs_from(Some("")), defaults);
+            assert_eq!(data_dirs_from(Some("relative")), defaults);
+            assert_eq!(
+                data_dirs_from(Some("/opt/share::relative:/usr/share")),
+                [PathBuf::from("/opt/share"), PathBuf::from("/usr/share")]
+            );
+
+            assert_eq!(
+                data_home_from(Some("/data".into()), Some("/home/me".into())),
+                Some(PathBuf::from("/data"))
+            );
+            assert_eq!(
+                data_home_from(Some("relative".into()), Some("/home/me".into())),
+                Some(PathBuf::from("/home/me/.local/share"))
+            );
+            assert_eq!(
+                data_home_from(Some("".into()), Some("relative".into())),
+                None
+            );
+            assert_eq!(data_home_from(None, None), None);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! How values are turned into the strings kept in a [`crate::epi::Storage`], see [`crate::epi::get_value`].
+
+use std::collections::BTreeMap;
+
+/// A format for the values in a [`crate::epi::Storage`].
+///
+/// Which one is used for a key is decided by [`crate::epi::Storage::codec`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Codec {
+    /// [RON](https://github.com/ron-rs/ron): human-readable and -editable, e.g. for settings.
+    #[default]
+    Ron,
+
+    /// JSON, e.g. for values shared with other tools.
+    #[cfg(feature = "json")]
+    Json,
+
+    /// [postcard](https://docs.rs/postcard), base64-encoded.
+    ///
+    /// Compact, but not self-describing, so only use it for values whose type doesn't change,
+    /// e.g. large caches.
+    #[cfg(feature = "postcard")]
+    Postcard,
+}
+
+/// A value could not be encoded or decoded with a [`Codec`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodecError {
+    pub codec: Codec,
+    pub message: String,
+}
+
+impl std::fmt::Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.codec, self.message)
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl Codec {
+    pub fn encode<T: serde::Serialize + ?Sized>(self, value: &T) -> Result<String, CodecError> {
+        let error = |err: &dyn std::fmt::Display| self.error(err);
+        match self {
+            Self::Ron => ron::ser::to_string(value).map_err(|err| error(&err)),
+
+            #[cfg(feature = "json")]
+            Self::Json => serde_json::to_string(value).map_err(|err| error(&err)),
+
+            #[cfg(feature = "postcard")]
+            Self::Postcard => {
+                use base64::Engine as _;
+                let bytes = postcard::to_allocvec(value).map_err(|err| error(&err))?;
+                Ok(base64::engine::general_purpose::STANDARD_NO_PAD.encode(bytes))
+            }
+        }
+    }
+
+    pub fn decode<T: serde::de::DeserializeOwned>(self, string: &str) -> Result<T, CodecError> {
+        let error = |err: &dyn std::fmt::Display| self.error(err);
+        match self {
+            Self::Ron => ron::from_str(string).map_err(|err| error(&err)),
+
+            #[cfg(feature = "json")]
+            Self::Json => serde_json::from_str(string).map_err(|err| error(&err)),
+
+            #[cfg(feature = "postcard")]
+            Self::Postcard => {
+                use base64::Engine as _;
+                let bytes = base64::engine::general_purpose::STANDARD_NO_PAD
+                    .decode(string)
+                    .map_err(|err| error(&err))?;
+                postcard::from_bytes(&bytes).map_err(|err| error(&err))
+            }
+        }
+    }
+
+    fn error(self, err: &dyn std::fmt::Display) -> CodecError {
+        CodecError {
+            codec: self,
+            message: err.to_string(),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Which [`Codec`] to use for which key, for implementing [`crate::epi::Storage::codec`].
+///
+/// ```
+/// use eframe_stripped::epi::{Codec, Codecs};
+///
+/// let codecs = Codecs::default().with_prefix("thumbnail/", Codec::Ron);
+/// assert_eq!(codecs.get("thumbnail/42"), Codec::Ron);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Codecs {
+    default: Codec,
+    keys: BTreeMap<String, Codec>,
+    prefixes: BTreeMap<String, Codec>,
+}
+
+impl Codecs {
+    /// Use `default` for all keys without a more specific codec.
+    pub fn new(default: Codec) -> Self {
+        Self {
+            default,
+            ..Default::default()
+        }
+    }
+
+    /// Use `codec` for exactly `key`.
+    #[inline]
+    pub fn with_key(mut self, key: impl Into<String>, codec: Codec) -> Self {
+        self.keys.insert(key.into(), codec);
+        self
+    }
+
+    /// Use `codec` for all keys starting with `prefix`, unless a longer prefix or the exact key matches.
+    #[inline]
+    pub fn with_prefix(mut self, prefix: impl Into<String>, codec: Codec) -> Self {
+        self.prefixes.insert(prefix.into(), codec);
+        self
+    }
+
+    /// The codec to use for `key`.
+    pub fn get(&self, key: &str) -> Codec {
+        if let Some(codec) = self.keys.get(key) {
+            return *codec;
+        }
+        self.prefixes
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, codec)| *codec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epi::{MemoryStorage, Storage as _, get_value, set_value};
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    enum Shape {
+        Circle { radius: f32 },
+        Rect(f32, f32),
+        Empty,
+    }
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Settings {
+        name: String,
+        scale: f64,
+        recent: Vec<String>,
+        window: Option<(i32, i32)>,
+        shapes: Vec<Shape>,
+        counts: BTreeMap<String, u64>,
+    }
+
+    fn settings() -> Settings {
+        Settings {
+            name: "quotes \" and\nnewlines".to_owned(),
+            scale: 1.25,
+            recent: vec!["a.txt".to_owned(), "ü/b.txt".to_owned()],
+            window: Some((-10, 20)),
+            shapes: vec![
+                Shape::Circle { radius: 0.5 },
+                Shape::Rect(1.0, 2.0),
+                Shape::Empty,
+            ],
+            counts: [("x".to_owned(), u64::MAX)].into_iter().collect(),
+        }
+    }
+
+    fn round_trip(codec: Codec) {
+        let encoded = codec.encode(&settings()).unwrap();
+        let decoded: Settings = codec.decode(&encoded).unwrap();
+        assert_eq!(decoded, settings());
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        round_trip(Codec::Ron);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_round_trip() {
+        round_trip(Codec::Json);
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn postcard_round_trip() {
+        round_trip(Codec::Postcard);
+    }
+
+    #[test]
+    fn decode_error_names_codec() {
+        let err = Codec::Ron.decode::<Settings>("(name: 3").unwrap_err();
+        assert_eq!(err.codec, Codec::Ron);
+    }
+
+    #[cfg(all(feature = "json", feature = "postcard"))]
+    #[test]
+    fn codec_per_key() {
+        let codecs = Codecs::new(Codec::Ron)
+            .with_prefix("cache/", Codec::Json)
+            .with_prefix("cache/big/", Codec::Postcard)
+            .with_key("cache/big/readme", Codec::Json);
+        assert_eq!(codecs.get("settings"), Codec::Ron);
+        assert_eq!(codecs.get("cache/small"), Codec::Json);
+        assert_eq!(codecs.get("cache/big/1"), Codec::Postcard);
+        assert_eq!(codecs.get("cache/big/readme"), Codec::Json);
+
+        let mut storage = MemoryStorage::new().with_codecs(codecs);
+        set_value(&mut storage, "settings", &settings());
+        set_value(&mut storage, "cache/big/1", &settings());
+        assert!(storage.get_string("settings").unwrap().starts_with('('));
+        assert_eq!(
+            Codec::Postcard
+                .decode::<Settings>(&storage.get_string("cache/big/1").unwrap())
+                .unwrap(),
+            settings()
+        );
+        assert_eq!(get_value(&storage, "cache/big/1"), Some(settings()));
+    }
+
+    #[test]
+    fn get_value_uses_storage_codec() {
+        let mut storage = MemoryStorage::new();
+        set_value(&mut storage, "settings", &settings());
+        assert_eq!(get_value(&storage, "settings"), Some(settings()));
+        assert_eq!(get_value::<Settings>(&storage, "missing"), None);
+    }
+}
@@ -0,0 +1,242 @@
+//! An overlay showing why and how often eframe repaints.
+//!
+//! See [`crate::NativeOptions::debug_overlay`].
+
+use std::{collections::VecDeque, time::Instant};
+
+use egui::{Color32, KeyboardShortcut, Rect, Sense, Stroke, vec2};
+use winit::{event_loop::ControlFlow, window::WindowId};
+
+use crate::epi::{FrameTimeHistory, IntegrationInfo};
+
+/// Frames slower than this are drawn in red in the graph.
+const TARGET_FRAME_TIME: f32 = 1.0 / 60.0;
+
+/// The state of the event loop after the last event was handled.
+struct EventLoopState {
+    control_flow: ControlFlow,
+    pending_repaints: Vec<(WindowId, Instant)>,
+}
+
+pub struct DebugOverlay {
+    visible: bool,
+    hotkey: Option<KeyboardShortcut>,
+
+    /// When the last few root frames started, for the FPS counter.
+    frame_starts: VecDeque<Instant>,
+
+    event_loop: Option<EventLoopState>,
+
+    /// Set by the integration before each frame.
+    pub viewport_count: usize,
+
+    /// Set by the integration before each frame.
+    pub texture_bytes: usize,
+}
+
+impl DebugOverlay {
+    pub fn new(native_options: &crate::NativeOptions) -> Self {
+        Self {
+            visible: native_options.debug_overlay,
+            hotkey: native_options.debug_overlay_hotkey,
+            frame_starts: VecDeque::with_capacity(FrameTimeHistory::DEFAULT_CAPACITY),
+            event_loop: None,
+            viewport_count: 0,
+            texture_bytes: 0,
+        }
+    }
+
+    /// Called by [`crate::winit_wrapper::WinitAppWrapper`] after it has decided when to wake up next.
+    pub fn set_event_loop_state(
+        &mut self,
+        control_flow: ControlFlow,
+        pending_repaints: impl Iterator<Item = (WindowId, Instant)>,
+    ) {
+        self.event_loop = Some(EventLoopState {
+            control_flow,
+            pending_repaints: pending_repaints.collect(),
+        });
+    }
+
+    /// Show the overlay if it is visible, toggling it first if the hotkey was pressed.
+    ///
+    /// Call once per root frame, after [`crate::App::update`].
+    /// Never requests a repaint by itself, so that it doesn't change what it is measuring.
+    pub fn show(&mut self, egui_ctx: &egui::Context, info: &IntegrationInfo) {
+        if let Some(hotkey) = &self.hotkey
+            && egui_ctx.input_mut(|i| i.consume_shortcut(hotkey))
+        {
+            self.visible = !self.visible;
+        }
+
+        let now = Instant::now();
+        if self.frame_starts.len() == FrameTimeHistory::DEFAULT_CAPACITY {
+            self.frame_starts.pop_front();
+        }
+        self.frame_starts.push_back(now);
+
+        if !self.visible {
+            return;
+        }
+
+        profiling::function_scope!();
+
+        egui::Window::new("eframe debug")
+            .id(egui::Id::new("eframe_debug_overlay"))
+            .default_pos(egui_ctx.screen_rect().right_top() + vec2(-280.0, 8.0))
+            .resizable(false)
+            .collapsible(true)
+            .show(egui_ctx, |ui| {
+                ui.label(format!(
+                    "FPS: {}",
+                    self.fps().map_or("–".to_owned(), |fps| format!("{fps:.1}"))
+                ));
+                frame_time_graph(ui, &info.frame_times);
+                frame_time_table(ui, &info.frame_times);
+
+                ui.separator();
+
+                ui.label(format!("Viewports: {}", self.viewport_count));
+                ui.label(format!(
+                    "Texture memory: {}",
+                    format_bytes(self.texture_bytes)
+                ));
+
+                ui.separator();
+
+                if let Some(event_loop) = &self.event_loop {
+                    ui.label(format!(
+                        "Control flow: {}",
+                        format_control_flow(event_loop.control_flow, now)
+                    ));
+                    if event_loop.pending_repaints.is_empty() {
+                        ui.label("No pending repaints");
+                    } else {
+                        ui.label("Pending repaints:");
+                        for (window_id, repaint_time) in &event_loop.pending_repaints {
+                            ui.label(format!(
+                                "  {window_id:?}: {}",
+                                format_instant(*repaint_time, now)
+                            ));
+                        }
+                    }
+                } else {
+                    ui.label("Control flow: unknown");
+                }
+            });
+    }
+
+    /// Frames per second over the recorded frames, counting the time between frame starts.
+    fn fps(&self) -> Option<f32> {
+        let first = self.frame_starts.front()?;
+        let last = self.frame_starts.back()?;
+        let seconds = last.duration_since(*first).as_secs_f32();
+        (seconds > 0.0).then(|| (self.frame_starts.len() - 1) as f32 / seconds)
+    }
+}
+
+/// One bar per frame: CPU time at the bottom, time spent in swap on top.
+fn frame_time_graph(ui: &mut egui::Ui, frame_times: &FrameTimeHistory) {
+    let (rect, _) = ui.allocate_exact_size(vec2(256.0, 64.0), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    // Leave room above the target frame time so slow frames stand out.
+    let max_seconds = 2.0 * TARGET_FRAME_TIME;
+    let y_from_seconds =
+        |seconds: f32| rect.bottom() - rect.height() * (seconds / max_seconds).min(1.0);
+
+    let bar_width = rect.width() / frame_times.capacity() as f32;
+    let first_x = rect.right() - bar_width * frame_times.len() as f32;
+    for (i, timings) in frame_times.iter().enumerate() {
+        let left = first_x + bar_width * i as f32;
+        let right = left + bar_width;
+        let cpu_color = if timings.cpu() > TARGET_FRAME_TIME {
+            Color32::RED
+        } else {
+            Color32::LIGHT_GREEN
+        };
+        painter.rect_filled(
+            Rect::from_x_y_ranges(left..=right, y_from_seconds(timings.cpu())..=rect.bottom()),
+            0.0,
+            cpu_color,
+        );
+        painter.rect_filled(
+            Rect::from_x_y_ranges(
+                left..=right,
+                y_from_seconds(timings.total())..=y_from_seconds(timings.cpu()),
+            ),
+            0.0,
+            Color32::GRAY,
+        );
+    }
+
+    let target_y = y_from_seconds(TARGET_FRAME_TIME);
+    painter.hline(
+        rect.x_range(),
+        target_y,
+        Stroke::new(1.0, ui.visuals().weak_text_color()),
+    );
+}
+
+fn frame_time_table(ui: &mut egui::Ui, frame_times: &FrameTimeHistory) {
+    type Part = fn(&crate::epi::FrameTimings) -> f32;
+    let parts: [(&str, Part); 5] = [
+        ("update", |t| t.update),
+        ("tessellate", |t| t.tessellate),
+        ("paint", |t| t.paint),
+        ("swap", |t| t.swap),
+        ("total", |t| t.total()),
+    ];
+
+    egui::Grid::new("eframe_debug_overlay_frame_times")
+        .num_columns(5)
+        .striped(true)
+        .show(ui, |ui| {
+            for header in ["ms", "min", "avg", "max", "p99"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+
+            for (name, part) in parts {
+                ui.label(name);
+                if let Some(stats) = frame_times.stats(part) {
+                    for seconds in [stats.min, stats.avg, stats.max, stats.p99] {
+                        ui.monospace(format!("{:.2}", 1e3 * seconds));
+                    }
+                } else {
+                    for _ in 0..4 {
+                        ui.label("–");
+                    }
+                }
+                ui.end_row();
+            }
+        });
+}
+
+fn format_control_flow(control_flow: ControlFlow, now: Instant) -> String {
+    match control_flow {
+        ControlFlow::Poll => "Poll".to_owned(),
+        ControlFlow::Wait => "Wait".to_owned(),
+        ControlFlow::WaitUntil(instant) => format!("WaitUntil({})", format_instant(instant, now)),
+    }
+}
+
+fn format_instant(instant: Instant, now: Instant) -> String {
+    if instant <= now {
+        "now".to_owned()
+    } else {
+        format!("in {:.1} ms", 1e3 * (instant - now).as_secs_f32())
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    let bytes = bytes as f64;
+    if bytes < 1024.0 {
+        format!("{bytes} B")
+    } else if bytes < 1024.0 * 1024.0 {
+        format!("{:.1} KiB", bytes / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes / (1024.0 * 1024.0))
+    }
+}
@@ -8,6 +8,15 @@
 #[cfg(any(feature = "glow"))]
 pub use crate::winit_integration::UserEvent;
 
+#[cfg(feature = "ron")]
+pub use crate::codec::{Codec, CodecError, Codecs};
+pub use crate::epi_native::open_viewports;
+#[cfg(feature = "persistence")]
+pub use crate::file_storage::storage_dir;
+pub use crate::migration::{AppMigrations, Migration, StorageIssue};
+pub use crate::monitor::Centering;
+pub use crate::storage::{MemoryStorage, OverlayStorage};
+
 #[cfg(not(target_arch = "wasm32"))]
 use raw_window_handle::{
     DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawDisplayHandle,
@@ -28,6 +37,16 @@ pub use winit::{event_loop::EventLoopBuilder, window::WindowAttributes};
 #[cfg(any(feature = "glow"))]
 pub type EventLoopBuilderHook = Box<dyn FnOnce(&mut EventLoopBuilder<UserEvent>)>;
 
+/// Pick the OpenGL config to use.
+///
+/// Gets every config matching the hardware acceleration, depth and stencil settings,
+/// sorted so the best match for the other [`NativeOptions`] comes first.
+/// Returning `None` makes [`crate::run_native`] fail with [`crate::Error::NoGlutinConfigs`].
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "glow")]
+pub type GlConfigPicker =
+    Box<dyn Fn(Vec<glutin::config::Config>) -> Option<glutin::config::Config>>;
+
 /// Hook into the building of a the native window.
 ///
 /// You can configure any platform specific details required on top of the default configuration
@@ -58,6 +77,16 @@ pub struct CreationContext<'s> {
     /// You can use the storage to restore app state(requires the "persistence" feature).
     pub storage: Option<&'s dyn Storage>,
 
+    /// Persisted state that could not be restored, and was backed up instead.
+    ///
+    /// egui's memory, the window settings and the app state at [`APP_KEY`] are versioned.
+    /// Values that fail to decode or migrate (see [`NativeOptions::app_migrations`]) are
+    /// moved out of the way, so the app can tell the user, or try to recover them itself.
+    ///
+    /// This also reports when the storage file was damaged and a backup was loaded instead
+    /// (see [`NativeOptions::storage_backups`]).
+    pub storage_issues: Vec<StorageIssue>,
+
     /// The [`glow::Context`] allows you to initialize OpenGL resources (e.g. shaders) that
     /// you might want to use later from a [`egui::PaintCallback`].
     ///
@@ -104,6 +133,7 @@ impl CreationContext<'_> {
             egui_ctx,
             integration_info: IntegrationInfo::mock(),
             storage: None,
+            storage_issues: Vec::new(),
             #[cfg(feature = "glow")]
             gl: None,
             #[cfg(feature = "glow")]
@@ -163,6 +193,19 @@ pub trait App {
     /// The path can be customized via [`NativeOptions::persistence_path`].
     fn save(&mut self, _storage: &mut dyn Storage) {}
 
+    /// Called when values in the storage were changed from outside,
+    /// e.g. by another instance of the app, or by editing the file by hand.
+    ///
+    /// `keys` are the keys whose values changed or were removed, and `storage` already has the new values.
+    /// Only called if the storage supports [`Storage::watch`].
+    fn storage_changed(&mut self, _storage: &dyn Storage, _keys: &[String]) {}
+
+    /// Called when the app is started again while [`NativeOptions::single_instance`] is set,
+    /// with the command-line arguments of the new instance (without the executable).
+    ///
+    /// The root window has already been asked to come to the front with [`egui::ViewportCommand::Focus`].
+    fn other_instance_started(&mut self, _args: Vec<std::ffi::OsString>) {}
+
     /// Called once on shutdown, after [`Self::save`].
     ///
     /// If you need to abort an exit check `ctx.input(|i| i.viewport().close_requested())`
@@ -179,6 +222,16 @@ pub trait App {
     #[cfg(not(feature = "glow"))]
     fn on_exit(&mut self) {}
 
+    /// Called after the OpenGL context was lost (e.g. due to a GPU reset or driver update) and
+    /// has been recreated.
+    ///
+    /// All GL resources you created yourself (textures, buffers, shaders, …) are gone,
+    /// including textures registered with [`Frame::register_native_glow_texture`],
+    /// so this is the place to recreate them using the new context.
+    /// egui's own textures are re-uploaded for you.
+    #[cfg(feature = "glow")]
+    fn on_gl_context_recreated(&mut self, _gl: Option<&std::sync::Arc<glow::Context>>) {}
+
     // ---------
     // Settings:
 
@@ -197,6 +250,10 @@ pub trait App {
     /// egui recommends that rendering backends use a normal "gamma-space" (non-sRGB-aware) blending,
     ///  which means the values you return here should also be in `sRGB` gamma-space in the 0-1 range.
     /// You can use [`egui::Color32::to_normalized_gamma_f32`] for this.
+    ///
+    /// The color is premultiplied, like [`egui::Color32`], which is what compositors expect
+    /// when the window is transparent (see [`IntegrationInfo::transparent`]).
+    /// Otherwise the alpha is ignored.
     fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
         // NOTE: a bright gray makes the shadows of the windows look weird.
         // We use a bit of transparency so that if the user switches on the
@@ -268,13 +325,68 @@ pub struct NativeOptions {
     ///
     /// If you don't set an icon, a default egui icon will be used.
     /// To avoid this, set the icon to [`egui::IconData::default`].
+    /// To give the app icon in several sizes, use [`set_app_icons`].
     pub viewport: egui::ViewportBuilder,
 
     /// Turn on vertical syncing, limiting the FPS to the display refresh rate.
     ///
+    /// Can be changed at runtime with [`Frame::set_vsync`].
+    ///
     /// The default is `true`.
     pub vsync: bool,
 
+    /// Limit how often each window is repainted, in frames per second.
+    ///
+    /// Unlike [`Self::vsync`] this works on all platforms and drivers.
+    /// Can be changed at runtime with [`Frame::set_max_fps`].
+    ///
+    /// The default is `None` (no limit).
+    pub max_fps: Option<f32>,
+
+    /// Show a window with FPS, frame times, pending repaints, control flow,
+    /// viewport count and texture memory on top of the app.
+    ///
+    /// Useful to figure out why the app repaints too often, or not at all.
+    /// Can also be toggled with [`Self::debug_overlay_hotkey`].
+    ///
+    /// The default is `false`.
+    pub debug_overlay: bool,
+
+    /// Shortcut that toggles [`Self::debug_overlay`] at runtime.
+    ///
+    /// The default is `None`.
+    pub debug_overlay_hotkey: Option<egui::KeyboardShortcut>,
+
+    /// Record every winit callback, event result, control flow change and user event
+    /// to this file as JSON lines. Compare two traces with the `trace_diff` tool.
+    ///
+    /// If `None`, the path is read from the `EFRAME_EVENT_TRACE` environment variable instead.
+    ///
+    /// The default is `None`.
+    pub event_trace_path: Option<std::path::PathBuf>,
+
+    /// Record the input of every frame to this file, e.g. to reproduce a bug report
+    /// with [`Self::replay_input`].
+    ///
+    /// The default is `None`.
+    #[cfg(feature = "input_recording")]
+    pub record_input: Option<std::path::PathBuf>,
+
+    /// Feed the input recorded with [`Self::record_input`] to the app instead of live input.
+    ///
+    /// Live input is used again once the recording is over.
+    ///
+    /// The default is `None`.
+    #[cfg(feature = "input_recording")]
+    pub replay_input: Option<std::path::PathBuf>,
+
+    /// Replay [`Self::replay_input`] without opening a window or painting anything,
+    /// and return from [`crate::run_native`] when the recording is over.
+    ///
+    /// The default is `false`.
+    #[cfg(feature = "input_recording")]
+    pub replay_headless: bool,
+
     /// Set the level of the multisampling anti-aliasing (MSAA).
     ///
     /// Must be a power-of-two. Higher = more smooth 3D.
@@ -284,8 +396,15 @@ pub struct NativeOptions {
     /// `egui` already performs anti-aliasing via "feathering"
     /// (controlled by [`egui::epaint::TessellationOptions`]),
     /// but if you are embedding 3D in egui you may want to turn on multisampling.
+    ///
+    /// If no config has exactly this many samples, the closest one with more samples is used.
     pub multisampling: u16,
 
+    /// Prefer an sRGB-capable framebuffer.
+    ///
+    /// `egui` does its own gamma handling, so the default is `false`.
+    pub srgb_framebuffer: bool,
+
     /// Sets the number of bits in the depth buffer.
     ///
     /// `egui` doesn't need the depth buffer, so the default value is 0.
@@ -344,21 +463,112 @@ pub struct NativeOptions {
     /// For OpenGL ES 2.0: set this to [`egui_glow::ShaderVersion::Es100`] to solve blank texture problem (by using the "fallback shader").
     pub shader_version: Option<egui_glow::ShaderVersion>,
 
+    /// Which platform API to create the OpenGL display with, e.g. EGL or GLX on Linux.
+    ///
+    /// The default is [`glutin_winit::ApiPreference::FallbackEgl`],
+    /// see <https://github.com/emilk/egui/pull/2526#issuecomment-1400229576> for why.
+    #[cfg(feature = "glow")]
+    pub gl_api_preference: glutin_winit::ApiPreference,
+
+    /// The OpenGL (ES) API and version to request,
+    /// e.g. `ContextApi::OpenGl(Some(Version::new(4, 3)))`.
+    ///
+    /// If `None` (default), the platform default is requested, with OpenGL ES as a fallback.
+    /// If set, there is no fallback and context creation fails if the request can't be met.
+    #[cfg(feature = "glow")]
+    pub gl_context_api: Option<glutin::context::ContextApi>,
+
+    /// Request a core or compatibility profile (desktop OpenGL only).
+    ///
+    /// `None` (default) leaves it up to the platform.
+    #[cfg(feature = "glow")]
+    pub gl_profile: Option<glutin::context::GlProfile>,
+
+    /// Request a debug context, and log all `KHR_debug` messages using [`log`].
+    ///
+    /// The default is `false`.
+    #[cfg(feature = "glow")]
+    pub gl_debug: bool,
+
+    /// How tolerant the OpenGL context should be to faults.
+    ///
+    /// Use [`glutin::context::Robustness::RobustLoseContextOnReset`] to be able to recover from GPU resets,
+    /// see [`App::on_gl_context_recreated`].
+    /// eframe then keeps a copy of egui's textures in memory, to upload them again.
+    ///
+    /// The default is [`glutin::context::Robustness::NotRobust`].
+    #[cfg(feature = "glow")]
+    pub gl_robustness: glutin::context::Robustness,
+
+    /// Pick the OpenGL config yourself, instead of using the best ranked one.
+    ///
+    /// Note: A [`NativeOptions`] clone will not include any `gl_config_picker` hook.
+    #[cfg(feature = "glow")]
+    pub gl_config_picker: Option<GlConfigPicker>,
+
     /// On desktop: make the window position to be centered at initialization.
     ///
+    /// Where it is centered is decided by [`Self::centering`].
+    ///
     /// Platform specific:
     ///
-    /// Wayland desktop currently not supported.
+    /// On Wayland the compositor decides where windows go, see [`Centering`].
     pub centered: bool,
 
+    /// Where to center the window if [`Self::centered`] is set.
+    ///
+    /// Other viewports can be centered with [`set_viewport_centering`].
+    pub centering: Centering,
+
+    /// Only run one instance of the app, keyed on [`egui::ViewportBuilder::app_id`]
+    /// (or the title argument to [`crate::run_native`]).
+    ///
+    /// If the app is already running, [`crate::run_native`] sends it the command-line arguments
+    /// and returns [`crate::RunOutcome::AlreadyRunning`].
+    /// The running instance focuses its root window and gets [`App::other_instance_started`].
+    ///
+    /// Only supported on Unix, using a socket in `$XDG_RUNTIME_DIR`.
+    ///
+    /// The default is `false`.
+    pub single_instance: bool,
+
+    /// On Wayland, install a `.desktop` entry and the app icons into `$XDG_DATA_HOME`,
+    /// since that is how compositors find the icon of a window.
+    ///
+    /// Turn this off if the app must not write outside [`Self::persistence_path`],
+    /// e.g. because a package already installs the entry.
+    /// Entries that eframe didn't write are never changed either way.
+    ///
+    /// The default is `true`.
+    pub install_desktop_entry: bool,
+
     /// Controls whether or not the native window position and size will be
     /// persisted (only if the "persistence" feature is enabled).
+    ///
+    /// This also covers deferred and immediate viewports, see [`set_persist_viewport_window`].
     pub persist_window: bool,
 
     /// The folder where `eframe` will store the app state. If not set, eframe will use a default
     /// data storage path for each target system.
     pub persistence_path: Option<std::path::PathBuf>,
 
+    /// The version of the app state at [`APP_KEY`], and how to migrate it from older versions.
+    ///
+    /// Only used with the "persistence" feature.
+    /// Values that can't be migrated are backed up and reported in [`CreationContext::storage_issues`].
+    pub app_migrations: AppMigrations,
+
+    /// Which [`Codec`] to use for which key of the storage, e.g. a binary format for large caches.
+    ///
+    /// The default is [`Codec::Ron`] for everything.
+    #[cfg(feature = "persistence")]
+    pub storage_codecs: Codecs,
+
+    /// How many previous versions of the storage file to keep, to recover from if it gets damaged.
+    ///
+    /// The default is 3.
+    pub storage_backups: usize,
+
     /// Controls whether to apply dithering to minimize banding artifacts.
     ///
     /// Dithering assumes an sRGB output and thus will apply noise to any input value that lies between
@@ -391,8 +601,23 @@ impl Clone for NativeOptions {
             #[cfg(any(feature = "glow"))]
             window_builder: None, // Skip any builder callbacks if cloning
 
+            #[cfg(feature = "glow")]
+            gl_config_picker: None, // Skip any picker callbacks if cloning
+
             persistence_path: self.persistence_path.clone(),
 
+            app_migrations: self.app_migrations.clone(),
+
+            #[cfg(feature = "persistence")]
+            storage_codecs: self.storage_codecs.clone(),
+
+            event_trace_path: self.event_trace_path.clone(),
+
+            #[cfg(feature = "input_recording")]
+            record_input: self.record_input.clone(),
+            #[cfg(feature = "input_recording")]
+            replay_input: self.replay_input.clone(),
+
             #[cfg(target_os = "android")]
             android_app: self.android_app.clone(),
 
@@ -408,7 +633,18 @@ impl Default for NativeOptions {
             viewport: Default::default(),
 
             vsync: true,
+            max_fps: None,
+            debug_overlay: false,
+            debug_overlay_hotkey: None,
+            event_trace_path: None,
+            #[cfg(feature = "input_recording")]
+            record_input: None,
+            #[cfg(feature = "input_recording")]
+            replay_input: None,
+            #[cfg(feature = "input_recording")]
+            replay_headless: false,
             multisampling: 0,
+            srgb_framebuffer: false,
             depth_buffer: 0,
             stencil_buffer: 0,
             hardware_acceleration: HardwareAcceleration::Preferred,
@@ -427,12 +663,42 @@ impl Default for NativeOptions {
             #[cfg(feature = "glow")]
             shader_version: None,
 
+            #[cfg(feature = "glow")]
+            gl_api_preference: glutin_winit::ApiPreference::FallbackEgl,
+
+            #[cfg(feature = "glow")]
+            gl_context_api: None,
+
+            #[cfg(feature = "glow")]
+            gl_profile: None,
+
+            #[cfg(feature = "glow")]
+            gl_debug: false,
+
+            #[cfg(feature = "glow")]
+            gl_robustness: glutin::context::Robustness::NotRobust,
+
+            #[cfg(feature = "glow")]
+            gl_config_picker: None,
+
             centered: false,
+            centering: Centering::default(),
+
+            single_instance: false,
+
+            install_desktop_entry: true,
 
             persist_window: true,
 
             persistence_path: None,
 
+            app_migrations: Default::default(),
+
+            #[cfg(feature = "persistence")]
+            storage_codecs: Default::default(),
+
+            storage_backups: 3,
+
             dithering: true,
 
             #[cfg(target_os = "android")]
@@ -598,6 +864,12 @@ pub struct Frame {
     /// A place where you can store custom data in a way that persists when you restart the app.
     pub(crate) storage: Option<Box<dyn Storage>>,
 
+    /// See [`Self::set_vsync`].
+    pub(crate) vsync: bool,
+
+    /// See [`Self::set_max_fps`].
+    pub(crate) max_fps: Option<f32>,
+
     /// A reference to the underlying [`glow`] (OpenGL) context.
     #[cfg(feature = "glow")]
     pub(crate) gl: Option<std::sync::Arc<glow::Context>>,
@@ -653,6 +925,8 @@ impl Frame {
             #[cfg(not(target_arch = "wasm32"))]
             raw_window_handle: Err(HandleError::NotSupported),
             storage: None,
+            vsync: true,
+            max_fps: None,
         }
     }
 
@@ -679,6 +953,35 @@ impl Frame {
         self.storage.as_deref_mut()
     }
 
+    /// Is vertical syncing requested? See [`Self::set_vsync`].
+    pub fn vsync(&self) -> bool {
+        self.vsync
+    }
+
+    /// Turn vertical syncing on or off for all viewports.
+    ///
+    /// Takes effect at the end of the current frame.
+    /// Not all platforms and drivers support this, see [`Self::set_max_fps`] for an alternative.
+    ///
+    /// The initial value comes from [`NativeOptions::vsync`].
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.vsync = vsync;
+    }
+
+    /// The current frame rate limit. See [`Self::set_max_fps`].
+    pub fn max_fps(&self) -> Option<f32> {
+        self.max_fps
+    }
+
+    /// Limit how often each window is repainted, e.g. to save battery.
+    ///
+    /// `None` means no limit, which together with [`Self::set_vsync`]`(false)` is useful for benchmarks.
+    ///
+    /// The initial value comes from [`NativeOptions::max_fps`].
+    pub fn set_max_fps(&mut self, max_fps: Option<f32>) {
+        self.max_fps = max_fps.filter(|max_fps| *max_fps > 0.0);
+    }
+
     /// A reference to the underlying [`glow`] (OpenGL) context.
     ///
     /// This can be used, for instance, to:
@@ -787,6 +1090,16 @@ pub struct IntegrationInfo {
     ///
     /// `None` if this is the first frame.
     pub cpu_usage: Option<f32>,
+
+    /// Is the root window actually transparent?
+    ///
+    /// `false` if transparency wasn't requested with [`egui::ViewportBuilder::with_transparent`],
+    /// or if no OpenGL config supporting it could be found.
+    /// Use this to switch to an opaque theme when needed.
+    pub transparent: bool,
+
+    /// Detailed timings of the last few frames of the root viewport.
+    pub frame_times: FrameTimeHistory,
 }
 
 impl IntegrationInfo {
@@ -808,7 +1121,126 @@ impl IntegrationInfo {
                 },
             },
             cpu_usage: None,
+            transparent: false,
+            frame_times: FrameTimeHistory::default(),
+        }
+    }
+}
+
+/// How long the different parts of a single frame took, in seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTimings {
+    /// [`App::update`], including any immediate viewports it shows.
+    pub update: f32,
+
+    /// Turning the egui shapes into triangles.
+    pub tessellate: f32,
+
+    /// Uploading textures and issuing the draw calls.
+    pub paint: f32,
+
+    /// Swapping the buffers, which includes waiting for vsync.
+    pub swap: f32,
+}
+
+impl FrameTimings {
+    /// Time spent on the CPU, i.e. everything except [`Self::swap`].
+    ///
+    /// This is what ends up in [`IntegrationInfo::cpu_usage`].
+    pub fn cpu(&self) -> f32 {
+        self.update + self.tessellate + self.paint
+    }
+
+    /// The whole frame, including vsync waiting.
+    pub fn total(&self) -> f32 {
+        self.cpu() + self.swap
+    }
+}
+
+/// Summary of one of the [`FrameTimings`] over a [`FrameTimeHistory`], in seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimingStats {
+    pub min: f32,
+    pub avg: f32,
+    pub max: f32,
+
+    /// 99th percentile: only one frame in a hundred was slower than this.
+    pub p99: f32,
+}
+
+/// The [`FrameTimings`] of the last few frames.
+#[derive(Clone, Debug)]
+pub struct FrameTimeHistory {
+    frames: std::collections::VecDeque<FrameTimings>,
+    capacity: usize,
+}
+
+impl Default for FrameTimeHistory {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl FrameTimeHistory {
+    /// Number of frames kept by default, around two seconds at 60 Hz.
+    pub const DEFAULT_CAPACITY: usize = 120;
+
+    /// Keep the timings of the last `capacity` frames.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            frames: std::collections::VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub(crate) fn push(&mut self, timings: FrameTimings) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(timings);
+    }
+
+    /// Maximum number of frames kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The timings of the previous frame.
+    pub fn latest(&self) -> Option<&FrameTimings> {
+        self.frames.back()
+    }
+
+    /// Oldest first.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &FrameTimings> + '_ {
+        self.frames.iter()
+    }
+
+    /// Statistics of the part of the frame selected by `part`, e.g. `|t| t.update` or [`FrameTimings::total`].
+    ///
+    /// `None` if no frames have been recorded yet.
+    pub fn stats(&self, part: impl Fn(&FrameTimings) -> f32) -> Option<TimingStats> {
+        let mut seconds: Vec<f32> = self.frames.iter().map(part).collect();
+        if seconds.is_empty() {
+            return None;
         }
+        seconds.sort_by(f32::total_cmp);
+
+        let p99_index = (seconds.len() * 99).div_ceil(100) - 1;
+        Some(TimingStats {
+            min: seconds[0],
+            avg: seconds.iter().sum::<f32>() / seconds.len() as f32,
+            max: seconds[seconds.len() - 1],
+            p99: seconds[p99_index],
+        })
     }
 }
 
@@ -829,33 +1261,231 @@ pub trait Storage {
 
     /// write-to-disk or similar
     fn flush(&mut self);
+
+    /// Remove the value for the given key, if any.
+    ///
+    /// The default implementation does nothing, for storages written before this was added:
+    /// the value silently stays, and [`Self::get_string`] keeps returning it.
+    /// [`Self::clear`] and resetting values in [`OverlayStorage`] rely on this,
+    /// so implement it if your storage can remove values.
+    fn remove(&mut self, _key: &str) {}
+
+    /// All keys that have a value, e.g. for a settings UI.
+    ///
+    /// The default implementation returns no keys.
+    fn keys(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Remove all values, by calling [`Self::remove`] for each of [`Self::keys`].
+    fn clear(&mut self) {
+        for key in self.keys() {
+            self.remove(&key);
+        }
+    }
+
+    /// Call `on_change` (from any thread) whenever the values are changed from outside,
+    /// e.g. by another instance of the app. [`Self::reload`] then picks up the changes.
+    ///
+    /// Returns `false` if changes from outside can't be detected, which is the default.
+    fn watch(&mut self, _on_change: Box<dyn Fn() + Send + Sync>) -> bool {
+        false
+    }
+
+    /// Pick up changes made from outside, and return the keys whose values changed or were removed.
+    ///
+    /// Values set here but not flushed yet win over changes from outside.
+    /// The default implementation does nothing.
+    fn reload(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The format of the value at `key`, used by [`get_value`] and [`set_value`].
+    ///
+    /// The default is [`Codec::Ron`] for every key. See [`Codecs`] for choosing per key.
+    #[cfg(feature = "ron")]
+    fn codec(&self, _key: &str) -> Codec {
+        Codec::Ron
+    }
 }
 
-/// Get and deserialize the [RON](https://github.com/ron-rs/ron) stored at the given key.
+/// Get and deserialize the value stored at the given key, using the [`Storage::codec`] of that key.
 #[cfg(feature = "ron")]
 pub fn get_value<T: serde::de::DeserializeOwned>(storage: &dyn Storage, key: &str) -> Option<T> {
     profiling::function_scope!(key);
     storage
         .get_string(key)
-        .and_then(|value| match ron::from_str(&value) {
+        .and_then(|value| match storage.codec(key).decode(&value) {
             Ok(value) => Some(value),
             Err(err) => {
                 // This happens on when we break the format, e.g. when updating egui.
-                log::debug!("Failed to decode RON: {err}");
+                log::debug!("Failed to decode {key:?}: {err}");
                 None
             }
         })
 }
 
-/// Serialize the given value as [RON](https://github.com/ron-rs/ron) and store with the given key.
+/// Serialize the given value with the [`Storage::codec`] of the given key, and store it there.
 #[cfg(feature = "ron")]
 pub fn set_value<T: serde::Serialize>(storage: &mut dyn Storage, key: &str, value: &T) {
     profiling::function_scope!(key);
-    match ron::ser::to_string(value) {
+    match storage.codec(key).encode(value) {
         Ok(string) => storage.set_string(key, string),
-        Err(err) => log::error!("eframe failed to encode data using ron: {}", err),
+        Err(err) => log::error!("eframe failed to encode {key:?}: {err}"),
     }
 }
 
 /// [`Storage`] key used for app
 pub const APP_KEY: &str = "app";
+
+// ----------------------------------------------------------------------------
+
+/// Whether the position and size of the window of the viewport `viewport_id` are restored
+/// when it is shown, and saved with the rest of the app. On by default.
+///
+/// [`egui::ViewportBuilder`] has no room for this, so set it before the viewport is first shown,
+/// e.g. next to the call to [`egui::Context::show_viewport_deferred`].
+/// Viewports whose window should always open where the builder says, like popups, should turn it off.
+pub fn set_persist_viewport_window(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+    persist: bool,
+) {
+    ctx.data_mut(|data| {
+        data.insert_temp(egui::Id::new(viewport_id), PersistViewportWindow(persist));
+    });
+}
+
+pub(crate) fn persist_viewport_window(ctx: &egui::Context, viewport_id: egui::ViewportId) -> bool {
+    ctx.data(|data| data.get_temp(egui::Id::new(viewport_id)))
+        .is_none_or(|PersistViewportWindow(persist)| persist)
+}
+
+/// Stored in the temporary data of the [`egui::Context`] by the [`egui::Id`] of the viewport.
+/// egui keys that data by type too, so nothing else can overwrite it.
+#[derive(Clone, Copy)]
+struct PersistViewportWindow(bool);
+
+/// Center the window of the viewport `viewport_id` when it is created, unless its position
+/// is restored from a previous session (see [`set_persist_viewport_window`]).
+///
+/// Like [`set_persist_viewport_window`], set this before the viewport is first shown.
+/// `None` (the default) uses the position in the [`egui::ViewportBuilder`].
+pub fn set_viewport_centering(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+    centering: Option<Centering>,
+) {
+    ctx.data_mut(|data| data.insert_temp(viewport_centering_id(viewport_id), centering));
+}
+
+pub(crate) fn viewport_centering(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+) -> Option<Centering> {
+    ctx.data(|data| data.get_temp(viewport_centering_id(viewport_id)))
+        .flatten()
+}
+
+fn viewport_centering_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("eframe_viewport_centering").with(viewport_id)
+}
+
+/// The app icon in several sizes, e.g. 16×16, 32×32 and 256×256, for the task bar, dock, app switcher and so on.
+///
+/// Each platform picks the size closest to what it shows, instead of scaling one image.
+/// When set, this is used instead of the icon of the root [`egui::ViewportBuilder`]
+/// for the app icon, so call it again to change the icon, e.g. to show a badge.
+/// Pass no icons to go back to the icon of the root viewport.
+///
+/// Without this, [`egui::ViewportCommand::Icon`] and [`egui::ViewportCommand::Title`]
+/// for the root viewport change the app icon and title.
+///
+/// Platform specific:
+///
+/// * X11: winit can only set one icon, so the largest one is used.
+/// * Wayland: the icons are installed for [`egui::ViewportBuilder::app_id`],
+///   and some compositors only pick up changed icons when the window is shown again.
+pub fn set_app_icons(ctx: &egui::Context, icons: Vec<std::sync::Arc<egui::IconData>>) {
+    ctx.data_mut(|data| data.insert_temp(app_icons_id(), icons));
+}
+
+pub(crate) fn app_icons(ctx: &egui::Context) -> Vec<std::sync::Arc<egui::IconData>> {
+    ctx.data(|data| data.get_temp(app_icons_id()))
+        .unwrap_or_default()
+}
+
+fn app_icons_id() -> egui::Id {
+    egui::Id::new("eframe_app_icons")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(update_seconds: impl IntoIterator<Item = f32>) -> FrameTimeHistory {
+        let mut history = FrameTimeHistory::new(1000);
+        for update in update_seconds {
+            history.push(FrameTimings {
+                update,
+                ..Default::default()
+            });
+        }
+        history
+    }
+
+    fn update_stats(history: &FrameTimeHistory) -> Option<TimingStats> {
+        history.stats(|timings| timings.update)
+    }
+
+    #[test]
+    fn stats_of_no_frames() {
+        assert_eq!(update_stats(&history([])), None);
+    }
+
+    #[test]
+    fn stats_of_one_frame() {
+        let stats = TimingStats {
+            min: 0.5,
+            avg: 0.5,
+            max: 0.5,
+            p99: 0.5,
+        };
+        assert_eq!(update_stats(&history([0.5])), Some(stats));
+    }
+
+    #[test]
+    fn stats_of_known_samples() {
+        // Out of order, to check that they are sorted.
+        let stats = update_stats(&history([4.0, 1.0, 3.0, 2.0])).unwrap();
+        assert_eq!(
+            (stats.min, stats.avg, stats.max, stats.p99),
+            (1.0, 2.5, 4.0, 4.0)
+        );
+
+        // The 99th of 100 samples: only the slowest one is above it.
+        let stats = update_stats(&history((1..=100).rev().map(|i| i as f32))).unwrap();
+        assert_eq!(
+            (stats.min, stats.avg, stats.max, stats.p99),
+            (1.0, 50.5, 100.0, 99.0)
+        );
+
+        let stats = update_stats(&history((1..=200).map(|i| i as f32))).unwrap();
+        assert_eq!(stats.p99, 198.0);
+    }
+
+    #[test]
+    fn history_keeps_the_last_frames() {
+        let mut history = FrameTimeHistory::new(2);
+        for update in [1.0, 2.0, 3.0] {
+            history.push(FrameTimings {
+                update,
+                ..Default::default()
+            });
+        }
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.latest().map(|timings| timings.update), Some(3.0));
+        let stats = update_stats(&history).unwrap();
+        assert_eq!((stats.min, stats.max), (2.0, 3.0));
+    }
+}
@@ -2,15 +2,25 @@
 
 use web_time::Instant;
 
-use std::path::PathBuf;
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 use winit::event_loop::ActiveEventLoop;
 
 use raw_window_handle::{HasDisplayHandle as _, HasWindowHandle as _};
 
-use egui::{DeferredViewportUiCallback, ViewportBuilder, ViewportId};
+use egui::{DeferredViewportUiCallback, ViewportBuilder, ViewportCommand, ViewportId};
 use egui_winit::{EventResponse, WindowSettings};
 
-use crate::epi;
+use crate::{
+    debug_overlay::DebugOverlay,
+    epi,
+    monitor::{MonitorPlacement, SavedWindow},
+};
 
 // #[cfg_attr(target_os = "ios", allow(dead_code, unused_variables, unused_mut))]
 // pub fn viewport_builder(
@@ -88,18 +98,19 @@ use crate::epi;
 //     }
 // }
 
-pub fn apply_window_settings(
-    window: &winit::window::Window,
-    window_settings: Option<WindowSettings>,
-) {
+pub fn apply_window_settings(window: &winit::window::Window, saved_window: Option<&SavedWindow>) {
     profiling::function_scope!();
-    if let Some(window_settings) = window_settings {
-        window_settings.initialize_window(window);
+    if let Some(saved_window) = saved_window {
+        saved_window.initialize_window(window);
     }
 }
 
+/// `None` if no monitors are reported, e.g. on some Wayland compositors.
 #[cfg(not(target_os = "ios"))]
-fn largest_monitor_point_size(egui_zoom_factor: f32, event_loop: &ActiveEventLoop) -> egui::Vec2 {
+fn largest_monitor_point_size(
+    egui_zoom_factor: f32,
+    event_loop: &ActiveEventLoop,
+) -> Option<egui::Vec2> {
     profiling::function_scope!();
     let mut max_size = egui::Vec2::ZERO;
 
@@ -116,34 +127,56 @@ fn largest_monitor_point_size(egui_zoom_factor: f32, event_loop: &ActiveEventLoo
         max_size = max_size.max(size);
     }
 
-    if max_size == egui::Vec2::ZERO {
-        egui::Vec2::splat(16000.0)
-    } else {
-        max_size
-    }
+    (max_size != egui::Vec2::ZERO).then_some(max_size)
 }
 
 // ----------------------------------------------------------------------------
 
 /// For loading/saving app state and/or egui memory to disk.
-pub fn create_storage(_app_name: &str) -> Option<Box<dyn epi::Storage>> {
+///
+/// Problems with the storage file, e.g. that a backup had to be used, are added to `_issues`.
+pub fn create_storage(
+    _app_name: &str,
+    _native_options: &epi::NativeOptions,
+    _issues: &mut Vec<epi::StorageIssue>,
+) -> Option<Box<dyn epi::Storage>> {
     #[cfg(feature = "persistence")]
     if let Some(storage) = super::file_storage::FileStorage::from_app_id(_app_name) {
-        return Some(Box::new(storage));
+        return Some(configure_file_storage(storage, _native_options, _issues));
     }
     None
 }
 
 #[expect(clippy::unnecessary_wraps)]
-pub fn create_storage_with_file(_file: impl Into<PathBuf>) -> Option<Box<dyn epi::Storage>> {
+pub fn create_storage_with_file(
+    _file: impl Into<PathBuf>,
+    _native_options: &epi::NativeOptions,
+    _issues: &mut Vec<epi::StorageIssue>,
+) -> Option<Box<dyn epi::Storage>> {
     #[cfg(feature = "persistence")]
-    return Some(Box::new(
+    return Some(configure_file_storage(
         super::file_storage::FileStorage::from_ron_filepath(_file),
+        _native_options,
+        _issues,
     ));
     #[cfg(not(feature = "persistence"))]
     None
 }
 
+#[cfg(feature = "persistence")]
+fn configure_file_storage(
+    mut storage: super::file_storage::FileStorage,
+    native_options: &epi::NativeOptions,
+    issues: &mut Vec<epi::StorageIssue>,
+) -> Box<dyn epi::Storage> {
+    issues.extend(storage.take_issues());
+    Box::new(
+        storage
+            .with_codecs(native_options.storage_codecs.clone())
+            .with_backups(native_options.storage_backups),
+    )
+}
+
 // ----------------------------------------------------------------------------
 
 /// Everything needed to make a winit-based integration for [`epi`].
@@ -164,7 +197,22 @@ pub struct EpiIntegration {
     can_drag_window: bool,
     #[cfg(feature = "persistence")]
     persist_window: bool,
+    #[cfg(feature = "persistence")]
+    app_state_version: u32,
     app_icon_setter: super::app_icon::AppTitleIconSetter,
+    pub debug_overlay: DebugOverlay,
+
+    /// Set when the storage was changed from outside, see [`epi::Storage::watch`].
+    storage_changed: Arc<AtomicBool>,
+
+    /// Arguments of instances started while we're running, see [`epi::NativeOptions::single_instance`].
+    #[cfg(unix)]
+    pub other_instances: Option<crate::single_instance::Received>,
+
+    #[cfg(feature = "input_recording")]
+    pub input_recorder: Option<crate::input_recording::InputRecorder>,
+    #[cfg(feature = "input_recording")]
+    pub input_replay: Option<crate::input_recording::InputReplay>,
 }
 
 impl EpiIntegration {
@@ -180,9 +228,15 @@ impl EpiIntegration {
             Box<dyn FnMut(glow::Texture) -> egui::TextureId>,
         >,
     ) -> Self {
-        let frame = epi::Frame {
-            info: epi::IntegrationInfo { cpu_usage: None },
+        let mut frame = epi::Frame {
+            info: epi::IntegrationInfo {
+                cpu_usage: None,
+                transparent: false,
+                frame_times: Default::default(),
+            },
             storage,
+            vsync: native_options.vsync,
+            max_fps: native_options.max_fps.filter(|max_fps| *max_fps > 0.0),
             #[cfg(feature = "glow")]
             gl,
             #[cfg(feature = "glow")]
@@ -191,6 +245,16 @@ impl EpiIntegration {
             raw_window_handle: window.window_handle().map(|h| h.as_raw()),
         };
 
+        let storage_changed = Arc::new(AtomicBool::new(false));
+        if let Some(storage) = frame.storage_mut() {
+            let storage_changed = storage_changed.clone();
+            let egui_ctx = egui_ctx.clone();
+            storage.watch(Box::new(move || {
+                storage_changed.store(true, Ordering::Relaxed);
+                egui_ctx.request_repaint();
+            }));
+        }
+
         let icon = native_options
             .viewport
             .icon
@@ -204,6 +268,8 @@ impl EpiIntegration {
                 .clone()
                 .unwrap_or_else(|| app_name.to_owned()),
             Some(icon),
+            native_options.viewport.app_id.clone(),
+            native_options.install_desktop_entry,
         );
 
         Self {
@@ -215,7 +281,17 @@ impl EpiIntegration {
             can_drag_window: false,
             #[cfg(feature = "persistence")]
             persist_window: native_options.persist_window,
+            #[cfg(feature = "persistence")]
+            app_state_version: native_options.app_migrations.version(),
             app_icon_setter,
+            debug_overlay: DebugOverlay::new(native_options),
+            storage_changed,
+            #[cfg(unix)]
+            other_instances: None,
+            #[cfg(feature = "input_recording")]
+            input_recorder: None,
+            #[cfg(feature = "input_recording")]
+            input_replay: None,
             beginning: Instant::now(),
             is_first_frame: true,
         }
@@ -254,8 +330,17 @@ impl EpiIntegration {
         egui_winit.on_window_event(window, event)
     }
 
-    pub fn pre_update(&mut self) {
-        self.app_icon_setter.update();
+    /// Call before [`Self::update`] for the root viewport.
+    ///
+    /// `builder` is the root viewport as it is now, so changes to its title and icon
+    /// reach the app icon too.
+    pub fn pre_update(&mut self, window: &winit::window::Window, builder: &ViewportBuilder) {
+        let mut icons = epi::app_icons(&self.egui_ctx);
+        if icons.is_empty() {
+            icons.extend(builder.icon.clone());
+        }
+        self.app_icon_setter
+            .update(window, builder.title.as_deref(), icons);
     }
 
     /// Run user code - this can create immediate viewports, so hold no locks over this!
@@ -269,6 +354,15 @@ impl EpiIntegration {
     ) -> egui::FullOutput {
         raw_input.time = Some(self.beginning.elapsed().as_secs_f64());
 
+        #[cfg(feature = "input_recording")]
+        let replaying = viewport_ui_cb.is_none() && self.replay_or_record(&mut raw_input);
+        #[cfg(not(feature = "input_recording"))]
+        let replaying = false;
+
+        if viewport_ui_cb.is_none() {
+            self.reload_storage_if_changed(app);
+        }
+
         let close_requested = raw_input.viewport().close_requested();
 
         app.raw_input_hook(&self.egui_ctx, &mut raw_input);
@@ -279,8 +373,21 @@ impl EpiIntegration {
                 profiling::scope!("viewport_callback");
                 viewport_ui_cb(egui_ctx);
             } else {
+                #[cfg(unix)]
+                if let Some(other_instances) = &self.other_instances {
+                    for args in other_instances.take() {
+                        egui_ctx.send_viewport_cmd_to(ViewportId::ROOT, ViewportCommand::Focus);
+                        app.other_instance_started(args);
+                    }
+                }
+
                 profiling::scope!("App::update");
                 app.update(egui_ctx, &mut self.frame);
+                self.debug_overlay.show(egui_ctx, &self.frame.info);
+                if replaying {
+                    // Recorded frames don't wait for live input.
+                    egui_ctx.request_repaint();
+                }
             }
         });
 
@@ -301,8 +408,31 @@ impl EpiIntegration {
         std::mem::take(&mut self.pending_full_output)
     }
 
-    pub fn report_frame_time(&mut self, seconds: f32) {
-        self.frame.info.cpu_usage = Some(seconds);
+    /// Swap `raw_input` of the root viewport for the next replayed frame, if replaying, then record it.
+    ///
+    /// Returns `true` while replaying.
+    #[cfg(feature = "input_recording")]
+    fn replay_or_record(&mut self, raw_input: &mut egui::RawInput) -> bool {
+        let mut replaying = false;
+        if let Some(replay) = &mut self.input_replay {
+            if let Some(recorded) = replay.next_frame() {
+                *raw_input = recorded;
+                replaying = true;
+            }
+            if replay.is_finished() {
+                log::info!("Input replay is over, switching to live input");
+                self.input_replay = None;
+            }
+        }
+        if let Some(recorder) = &mut self.input_recorder {
+            recorder.record(raw_input);
+        }
+        replaying
+    }
+
+    pub fn report_frame_time(&mut self, timings: epi::FrameTimings) {
+        self.frame.info.cpu_usage = Some(timings.cpu());
+        self.frame.info.frame_times.push(timings);
     }
 
     pub fn post_rendering(&mut self, window: &winit::window::Window) {
@@ -320,39 +450,94 @@ impl EpiIntegration {
         &mut self,
         app: &mut dyn epi::App,
         window: Option<&winit::window::Window>,
+        viewports: &[PersistedViewport],
     ) {
         let now = Instant::now();
         if now - self.last_auto_save > app.auto_save_interval() {
-            self.save(app, window);
+            self.save(app, window, viewports);
             self.last_auto_save = now;
         }
     }
 
+    /// Let the app know if the storage was changed from outside.
+    fn reload_storage_if_changed(&mut self, app: &mut dyn epi::App) {
+        if !self.storage_changed.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        if let Some(storage) = self.frame.storage_mut() {
+            profiling::function_scope!();
+            let keys = storage.reload();
+            if !keys.is_empty() {
+                log::debug!("Storage changed from outside: {keys:?}");
+                app.storage_changed(storage, &keys);
+            }
+        }
+    }
+
     #[allow(clippy::unused_self, clippy::allow_attributes)]
-    pub fn save(&mut self, _app: &mut dyn epi::App, _window: Option<&winit::window::Window>) {
+    pub fn save(
+        &mut self,
+        _app: &mut dyn epi::App,
+        _window: Option<&winit::window::Window>,
+        _viewports: &[PersistedViewport],
+    ) {
         #[cfg(feature = "persistence")]
         if let Some(storage) = self.frame.storage_mut() {
+            use crate::migration::{EGUI_STATE_VERSION, version_key};
+
             profiling::function_scope!();
 
-            if let Some(window) = _window {
-                if self.persist_window {
-                    profiling::scope!("native_window");
-                    epi::set_value(
-                        storage,
-                        STORAGE_WINDOW_KEY,
-                        &WindowSettings::from_window(self.egui_ctx.zoom_factor(), window),
-                    );
+            if let Some(window) = _window
+                && self.persist_window
+            {
+                profiling::scope!("native_window");
+                let saved = SavedWindow::from_window(self.egui_ctx.zoom_factor(), window);
+                epi::set_value(storage, STORAGE_WINDOW_KEY, &saved.settings);
+                storage.set_string(
+                    &version_key(STORAGE_WINDOW_KEY),
+                    EGUI_STATE_VERSION.to_owned(),
+                );
+                match &saved.placement {
+                    Some(placement) => {
+                        epi::set_value(storage, STORAGE_WINDOW_PLACEMENT_KEY, placement);
+                        storage.set_string(
+                            &version_key(STORAGE_WINDOW_PLACEMENT_KEY),
+                            EGUI_STATE_VERSION.to_owned(),
+                        );
+                    }
+                    None => {
+                        storage.remove(STORAGE_WINDOW_PLACEMENT_KEY);
+                        storage.remove(&version_key(STORAGE_WINDOW_PLACEMENT_KEY));
+                    }
                 }
             }
+            if self.persist_window {
+                profiling::scope!("viewport_windows");
+                epi::set_value(storage, STORAGE_VIEWPORTS_KEY, &_viewports);
+                storage.set_string(
+                    &version_key(STORAGE_VIEWPORTS_KEY),
+                    EGUI_STATE_VERSION.to_owned(),
+                );
+            }
             if _app.persist_egui_memory() {
                 profiling::scope!("egui_memory");
                 self.egui_ctx
                     .memory(|mem| epi::set_value(storage, STORAGE_EGUI_MEMORY_KEY, mem));
+                storage.set_string(
+                    &version_key(STORAGE_EGUI_MEMORY_KEY),
+                    EGUI_STATE_VERSION.to_owned(),
+                );
             }
             {
                 profiling::scope!("App::save");
                 _app.save(storage);
             }
+            if storage.get_string(epi::APP_KEY).is_some() {
+                storage.set_string(
+                    &version_key(epi::APP_KEY),
+                    self.app_state_version.to_string(),
+                );
+            }
 
             profiling::scope!("Storage::flush");
             storage.flush();
@@ -376,21 +561,118 @@ fn from_image(image: image::DynamicImage) -> egui::IconData {
 }
 
 #[cfg(feature = "persistence")]
-const STORAGE_EGUI_MEMORY_KEY: &str = "egui";
+pub(crate) const STORAGE_EGUI_MEMORY_KEY: &str = "egui";
+
+#[cfg(feature = "persistence")]
+pub(crate) const STORAGE_WINDOW_KEY: &str = "window";
+
+/// The monitor the root window was on, see [`MonitorPlacement`].
+#[cfg(feature = "persistence")]
+pub(crate) const STORAGE_WINDOW_PLACEMENT_KEY: &str = "window_placement";
 
+/// The windows of all viewports except the root, as a list of [`PersistedViewport`].
 #[cfg(feature = "persistence")]
-const STORAGE_WINDOW_KEY: &str = "window";
+pub(crate) const STORAGE_VIEWPORTS_KEY: &str = "viewports";
+
+/// The window of a viewport other than the root, as it was when the app was saved.
+///
+/// Viewports are identified by their [`ViewportId`], so this only works for viewports
+/// whose id is the same every run, e.g. from [`ViewportId::from_hash_of`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+pub struct PersistedViewport {
+    pub id: ViewportId,
+
+    /// Was the viewport open when the app was saved?
+    pub open: bool,
+
+    pub window: WindowSettings,
+
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub placement: Option<MonitorPlacement>,
+}
+
+impl PersistedViewport {
+    pub fn new(id: ViewportId, open: bool, saved_window: SavedWindow) -> Self {
+        Self {
+            id,
+            open,
+            window: saved_window.settings,
+            placement: saved_window.placement,
+        }
+    }
+}
 
-pub fn load_window_settings(_storage: Option<&dyn epi::Storage>) -> Option<WindowSettings> {
+pub fn load_window_settings(_storage: Option<&dyn epi::Storage>) -> Option<SavedWindow> {
     profiling::function_scope!();
     #[cfg(feature = "persistence")]
     {
-        epi::get_value(_storage?, STORAGE_WINDOW_KEY)
+        let storage = _storage?;
+        Some(SavedWindow {
+            settings: epi::get_value(storage, STORAGE_WINDOW_KEY)?,
+            placement: epi::get_value(storage, STORAGE_WINDOW_PLACEMENT_KEY),
+        })
     }
     #[cfg(not(feature = "persistence"))]
     None
 }
 
+fn load_persisted_viewports(_storage: Option<&dyn epi::Storage>) -> Vec<PersistedViewport> {
+    #[cfg(feature = "persistence")]
+    if let Some(storage) = _storage {
+        return epi::get_value(storage, STORAGE_VIEWPORTS_KEY).unwrap_or_default();
+    }
+    Vec::new()
+}
+
+pub fn load_viewport_window_settings(
+    storage: Option<&dyn epi::Storage>,
+) -> egui::ViewportIdMap<SavedWindow> {
+    profiling::function_scope!();
+    load_persisted_viewports(storage)
+        .into_iter()
+        .map(|viewport| {
+            let saved_window = SavedWindow {
+                settings: viewport.window,
+                placement: viewport.placement,
+            };
+            (viewport.id, saved_window)
+        })
+        .collect()
+}
+
+/// What to save of the viewports other than the root: the `open` ones with their current window,
+/// and the `closed` ones with the window they had when they were closed, or when last saved.
+pub fn persisted_viewports(
+    open: impl IntoIterator<Item = (ViewportId, SavedWindow)>,
+    closed: &egui::ViewportIdMap<SavedWindow>,
+) -> Vec<PersistedViewport> {
+    let mut persisted: Vec<PersistedViewport> = open
+        .into_iter()
+        .map(|(id, saved_window)| PersistedViewport::new(id, true, saved_window))
+        .collect();
+    for (id, saved_window) in closed {
+        if !persisted.iter().any(|viewport| viewport.id == *id) {
+            persisted.push(PersistedViewport::new(*id, false, saved_window.clone()));
+        }
+    }
+    persisted.sort_by_key(|viewport| viewport.id.0.value());
+    persisted
+}
+
+/// The viewports (other than the root) that were open when the app was last saved.
+///
+/// Use this in your [`epi::AppCreator`] to open them again.
+/// Their position and size are restored when they are shown,
+/// unless turned off with [`epi::set_persist_viewport_window`].
+pub fn open_viewports(storage: Option<&dyn epi::Storage>) -> Vec<ViewportId> {
+    load_persisted_viewports(storage)
+        .into_iter()
+        .filter(|viewport| viewport.open)
+        .map(|viewport| viewport.id)
+        .collect()
+}
+
 pub fn load_egui_memory(_storage: Option<&dyn epi::Storage>) -> Option<egui::Memory> {
     profiling::function_scope!();
     #[cfg(feature = "persistence")]
@@ -406,7 +688,7 @@ pub fn viewport_builder(
     egui_zoom_factor: f32,
     event_loop: &ActiveEventLoop,
     native_options: &mut epi::NativeOptions,
-    window_settings: Option<WindowSettings>,
+    saved_window: Option<SavedWindow>,
 ) -> ViewportBuilder {
     profiling::function_scope!();
 
@@ -419,58 +701,40 @@ pub fn viewport_builder(
     // Always use the default window size / position on iOS. Trying to restore the previous position
     // causes the window to be shown too small.
     #[cfg(not(target_os = "ios"))]
-    let inner_size_points = if let Some(mut window_settings) = window_settings {
+    if let Some(mut saved_window) = saved_window {
         // Restore pos/size from previous session
-
-        if clamp_size_to_monitor_size {
-            window_settings.clamp_size_to_sane_values(largest_monitor_point_size(
-                egui_zoom_factor,
-                event_loop,
-            ));
-        }
-        window_settings.clamp_position_to_monitors(egui_zoom_factor, event_loop);
-
-        viewport_builder = window_settings.initialize_viewport_builder(
+        viewport_builder = restore_window_settings(
             egui_zoom_factor,
             event_loop,
             viewport_builder,
+            &mut saved_window,
         );
-        window_settings.inner_size_points()
     } else {
         if let Some(pos) = viewport_builder.position {
             viewport_builder = viewport_builder.with_position(pos);
         }
 
-        if clamp_size_to_monitor_size {
-            if let Some(initial_window_size) = viewport_builder.inner_size {
-                let initial_window_size = egui::NumExt::at_most(
-                    initial_window_size,
-                    largest_monitor_point_size(egui_zoom_factor, event_loop),
-                );
-                viewport_builder = viewport_builder.with_inner_size(initial_window_size);
-            }
+        if clamp_size_to_monitor_size
+            && let Some(initial_window_size) = viewport_builder.inner_size
+            && let Some(largest_monitor_size) =
+                largest_monitor_point_size(egui_zoom_factor, event_loop)
+        {
+            let initial_window_size =
+                egui::NumExt::at_most(initial_window_size, largest_monitor_size);
+            viewport_builder = viewport_builder.with_inner_size(initial_window_size);
         }
-
-        viewport_builder.inner_size
-    };
+    }
 
     #[cfg(not(target_os = "ios"))]
     if native_options.centered {
-        profiling::scope!("center");
-        if let Some(monitor) = event_loop
-            .primary_monitor()
-            .or_else(|| event_loop.available_monitors().next())
-        {
-            let monitor_size = monitor
-                .size()
-                .to_logical::<f32>(egui_zoom_factor as f64 * monitor.scale_factor());
-            let inner_size = inner_size_points.unwrap_or(egui::Vec2 { x: 800.0, y: 600.0 });
-            if 0.0 < monitor_size.width && 0.0 < monitor_size.height {
-                let x = (monitor_size.width - inner_size.x) / 2.0;
-                let y = (monitor_size.height - inner_size.y) / 2.0;
-                viewport_builder = viewport_builder.with_position([x, y]);
-            }
-        }
+        // No windows yet, so no parent or cursor position.
+        viewport_builder = native_options.centering.center(
+            egui_zoom_factor,
+            event_loop,
+            viewport_builder,
+            None,
+            None,
+        );
     }
 
     match std::mem::take(&mut native_options.window_builder) {
@@ -478,3 +742,109 @@ pub fn viewport_builder(
         None => viewport_builder,
     }
 }
+
+/// Restore the position and size from a previous session.
+///
+/// If we know which monitor the window was on, it goes back onto that one,
+/// or onto the primary monitor if it's gone. Otherwise it's clamped to the current monitors.
+#[cfg(not(target_os = "ios"))]
+pub fn restore_window_settings(
+    egui_zoom_factor: f32,
+    event_loop: &ActiveEventLoop,
+    viewport_builder: ViewportBuilder,
+    saved_window: &mut SavedWindow,
+) -> ViewportBuilder {
+    profiling::function_scope!();
+
+    let settings = &mut saved_window.settings;
+
+    // On some Linux systems, a window size larger than the monitor causes crashes,
+    // and on Windows the window does not appear at all.
+    if viewport_builder.clamp_size_to_monitor_size.unwrap_or(true)
+        && let Some(largest_monitor_size) = largest_monitor_point_size(egui_zoom_factor, event_loop)
+    {
+        settings.clamp_size_to_sane_values(largest_monitor_size);
+    }
+
+    match &saved_window.placement {
+        Some(placement) => {
+            let viewport_builder = settings.initialize_viewport_builder(
+                egui_zoom_factor,
+                event_loop,
+                viewport_builder,
+            );
+            placement.restore(egui_zoom_factor, event_loop, viewport_builder)
+        }
+        None => {
+            settings.clamp_position_to_monitors(egui_zoom_factor, event_loop);
+            settings.initialize_viewport_builder(egui_zoom_factor, event_loop, viewport_builder)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(viewports: &[PersistedViewport]) -> Vec<(ViewportId, bool)> {
+        viewports
+            .iter()
+            .map(|viewport| (viewport.id, viewport.open))
+            .collect()
+    }
+
+    #[test]
+    fn persisted_viewports_include_closed_windows() {
+        let [a, b, c] = ["a", "b", "c"].map(ViewportId::from_hash_of);
+        let closed: egui::ViewportIdMap<SavedWindow> = [a, c]
+            .into_iter()
+            .map(|id| (id, SavedWindow::default()))
+            .collect();
+
+        let persisted = persisted_viewports(
+            [(a, SavedWindow::default()), (b, SavedWindow::default())],
+            &closed,
+        );
+
+        let mut expected = vec![(a, true), (b, true), (c, false)];
+        expected.sort_by_key(|(id, _)| id.0.value());
+        assert_eq!(ids(&persisted), expected);
+    }
+
+    #[test]
+    fn persist_viewport_window_is_on_by_default() {
+        let ctx = egui::Context::default();
+        let [a, b] = ["a", "b"].map(ViewportId::from_hash_of);
+        epi::set_persist_viewport_window(&ctx, a, false);
+        assert!(!epi::persist_viewport_window(&ctx, a));
+        assert!(epi::persist_viewport_window(&ctx, b));
+
+        // Other data under the same id doesn't count.
+        ctx.data_mut(|data| data.insert_temp(egui::Id::new(b), false));
+        assert!(epi::persist_viewport_window(&ctx, b));
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn open_viewports_are_the_ones_open_when_saved() {
+        let [a, b] = ["a", "b"].map(ViewportId::from_hash_of);
+        let mut storage = epi::MemoryStorage::new();
+        assert_eq!(open_viewports(Some(&storage)), []);
+
+        let persisted = persisted_viewports(
+            [(a, SavedWindow::default())],
+            &[(b, SavedWindow::default())].into_iter().collect(),
+        );
+        epi::set_value(&mut storage, STORAGE_VIEWPORTS_KEY, &persisted);
+
+        assert_eq!(open_viewports(Some(&storage)), [a]);
+        let mut restored: Vec<_> = load_viewport_window_settings(Some(&storage))
+            .into_keys()
+            .collect();
+        restored.sort_by_key(|id| id.0.value());
+        let mut expected = vec![a, b];
+        expected.sort_by_key(|id| id.0.value());
+        assert_eq!(restored, expected);
+        assert_eq!(open_viewports(None), []);
+    }
+}
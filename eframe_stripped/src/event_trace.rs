@@ -0,0 +1,156 @@
+//! Records what the event loop does as JSON lines, so two runs can be compared with `trace_diff`.
+//!
+//! Each line is an object with these keys:
+//! * `t`: seconds since the trace was started.
+//! * `thread`: name (or id, if unnamed) of the thread the event was handled on.
+//! * `kind`: what happened, e.g. `window_event` or `control_flow`.
+//! * `detail`: what exactly happened, e.g. `RedrawRequested`. Stable between runs.
+//! * `window`: the window involved, if any. Window ids differ between runs.
+//! * `delay_ms`: for scheduled repaints and `WaitUntil`, how far in the future they are.
+
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{LineWriter, Write as _},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use winit::{event_loop::ControlFlow, window::WindowId};
+
+use crate::winit_integration::EventResult;
+
+/// Environment variable with the path to write a trace to, if [`crate::NativeOptions::event_trace_path`] is unset.
+pub const EVENT_TRACE_ENV_VAR: &str = "EFRAME_EVENT_TRACE";
+
+pub struct EventTrace {
+    path: PathBuf,
+    writer: LineWriter<File>,
+    start: Instant,
+
+    /// Only changes of the control flow are recorded.
+    last_control_flow: Option<String>,
+}
+
+impl EventTrace {
+    /// Start a trace at `path`, or at [`EVENT_TRACE_ENV_VAR`] if that is `None`.
+    ///
+    /// Returns `None` if tracing wasn't requested, or the file couldn't be created.
+    pub fn new(path: Option<&Path>) -> Option<Self> {
+        let path = match path {
+            Some(path) => path.to_owned(),
+            None => PathBuf::from(std::env::var_os(EVENT_TRACE_ENV_VAR)?),
+        };
+
+        match File::create(&path) {
+            Ok(file) => {
+                log::info!("Recording event loop trace to {}", path.display());
+                Some(Self {
+                    path,
+                    writer: LineWriter::new(file),
+                    start: Instant::now(),
+                    last_control_flow: None,
+                })
+            }
+            Err(err) => {
+                log::warn!("Failed to create event trace {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    /// A winit callback was called.
+    pub fn callback(&mut self, callback: &str, window_id: Option<WindowId>, detail: &str) {
+        self.record(callback, detail, window_id, None);
+    }
+
+    pub fn event_result(&mut self, event_result: &Result<EventResult, crate::Error>) {
+        let now = Instant::now();
+        match event_result {
+            Ok(EventResult::Wait) => self.record("event_result", "Wait", None, None),
+            Ok(EventResult::RepaintNow(window_id)) => {
+                self.record("event_result", "RepaintNow", Some(*window_id), None);
+            }
+            Ok(EventResult::RepaintNext(window_id)) => {
+                self.record("event_result", "RepaintNext", Some(*window_id), None);
+            }
+            Ok(EventResult::RepaintAt(window_id, when)) => {
+                let delay = when.saturating_duration_since(now).as_secs_f64();
+                self.record("event_result", "RepaintAt", Some(*window_id), Some(delay));
+            }
+            Ok(EventResult::Save) => self.record("event_result", "Save", None, None),
+            Ok(EventResult::Exit) => self.record("event_result", "Exit", None, None),
+            Err(err) => self.record(
+                "event_result",
+                &format!("Err({:?})", err.kind()),
+                None,
+                None,
+            ),
+        }
+    }
+
+    /// Records `control_flow` if it differs from the last one.
+    pub fn control_flow(&mut self, control_flow: ControlFlow) {
+        let (detail, delay) = match control_flow {
+            ControlFlow::Poll => ("Poll", None),
+            ControlFlow::Wait => ("Wait", None),
+            ControlFlow::WaitUntil(when) => (
+                "WaitUntil",
+                Some(when.saturating_duration_since(Instant::now()).as_secs_f64()),
+            ),
+        };
+        if self.last_control_flow.as_deref() != Some(detail) {
+            self.last_control_flow = Some(detail.to_owned());
+            self.record("control_flow", detail, None, delay);
+        }
+    }
+
+    pub fn request_redraw(&mut self, window_id: WindowId) {
+        self.record("request_redraw", "", Some(window_id), None);
+    }
+
+    pub fn exit(&mut self) {
+        self.record("exit", "", None, None);
+    }
+
+    fn record(
+        &mut self,
+        kind: &str,
+        detail: &str,
+        window_id: Option<WindowId>,
+        delay_sec: Option<f64>,
+    ) {
+        let thread = std::thread::current();
+        let thread = thread
+            .name()
+            .map_or_else(|| format!("{:?}", thread.id()), ToOwned::to_owned);
+
+        let mut line = format!(
+            r#"{{"t":{:.6},"thread":{},"kind":{},"detail":{}"#,
+            self.start.elapsed().as_secs_f64(),
+            json_string(&thread),
+            json_string(kind),
+            json_string(detail),
+        );
+        if let Some(window_id) = window_id {
+            write!(
+                line,
+                r#","window":{}"#,
+                json_string(&format!("{window_id:?}"))
+            )
+            .ok();
+        }
+        if let Some(delay_sec) = delay_sec {
+            write!(line, r#","delay_ms":{:.3}"#, 1e3 * delay_sec).ok();
+        }
+        line.push_str("}\n");
+
+        if let Err(err) = self.writer.write_all(line.as_bytes()) {
+            log::warn!("Failed to write event trace {}: {err}", self.path.display());
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    serde_json::Value::from(s).to_string()
+}
@@ -0,0 +1,718 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use crate::epi::{Storage, StorageIssue};
+
+/// The folder where `eframe` will store its state.
+///
+/// The given `app_id` is either the
+/// [`egui::ViewportBuilder::app_id`] of [`crate::NativeOptions::viewport`]
+/// or the title argument to [`crate::run_native`].
+///
+/// On native the path is picked using [`directories::ProjectDirs::data_dir`](https://docs.rs/directories/latest/directories/struct.ProjectDirs.html#method.data_dir) which is:
+/// * Linux:   `/home/UserName/.local/share/APP_ID`
+/// * macOS:   `/Users/UserName/Library/Application Support/APP_ID`
+/// * Windows: `C:\Users\UserName\AppData\Roaming\APP_ID\data`
+pub fn storage_dir(app_id: &str) -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", app_id)
+        .map(|proj_dirs| proj_dirs.data_dir().to_path_buf())
+}
+
+// ----------------------------------------------------------------------------
+
+/// A key-value store backed by a [RON](https://github.com/ron-rs/ron) file on disk.
+/// Used to restore egui state, glow window position/size and app state.
+///
+/// The file starts with a line holding a checksum of the rest, so a half-written or otherwise damaged
+/// file is detected. After editing the file by hand, remove that line.
+/// Each write goes to a temporary file first, which then replaces the old file, and the last
+/// [`Self::with_backups`] versions are kept next to it as `<file>.1` (newest), `<file>.2`, and so on.
+///
+/// If the file is damaged, the newest valid backup is loaded instead, and the damaged file is moved
+/// to `<file>.corrupt`. See [`Self::take_issues`].
+///
+/// Several instances of an app can share the file: reads and writes hold an advisory lock on
+/// `<file>.lock`, and [`Storage::flush`] only writes the keys changed by this instance,
+/// on top of whatever is in the file by then. So the last instance to write a key wins.
+/// Changes by others are picked up with [`Storage::watch`] and [`Storage::reload`].
+pub struct FileStorage {
+    ron_filepath: PathBuf,
+    kv: HashMap<String, String>,
+
+    /// Keys set or removed since the last flush.
+    changed: HashSet<String>,
+
+    last_save_join_handle: Option<std::thread::JoinHandle<()>>,
+    codecs: crate::epi::Codecs,
+    backups: usize,
+    issues: Vec<StorageIssue>,
+    watcher: Option<notify::RecommendedWatcher>,
+}
+
+impl Drop for FileStorage {
+    fn drop(&mut self) {
+        self.wait_for_save();
+    }
+}
+
+impl FileStorage {
+    /// Number of backups kept by default.
+    pub const DEFAULT_BACKUPS: usize = 3;
+
+    /// Store the state in this .ron file.
+    pub(crate) fn from_ron_filepath(ron_filepath: impl Into<PathBuf>) -> Self {
+        profiling::function_scope!();
+        let ron_filepath: PathBuf = ron_filepath.into();
+        log::debug!("Loading app state from {:?}…", ron_filepath);
+        let (kv, issues) = load(&ron_filepath);
+        // After recovering from a backup, the file doesn't have what we loaded,
+        // and flushing only our changes would drop everything else.
+        let changed = if issues.is_empty() {
+            Default::default()
+        } else {
+            kv.keys().cloned().collect()
+        };
+        Self {
+            kv,
+            ron_filepath,
+            changed,
+            last_save_join_handle: None,
+            codecs: Default::default(),
+            backups: Self::DEFAULT_BACKUPS,
+            issues,
+            watcher: None,
+        }
+    }
+
+    /// Find a good place to put the files that the OS likes.
+    pub fn from_app_id(app_id: &str) -> Option<Self> {
+        profiling::function_scope!();
+        if let Some(data_dir) = storage_dir(app_id) {
+            if let Err(err) = std::fs::create_dir_all(&data_dir) {
+                log::warn!(
+                    "Saving disabled: Failed to create app path at {:?}: {}",
+                    data_dir,
+                    err
+                );
+                None
+            } else {
+                Some(Self::from_ron_filepath(data_dir.join("app.ron")))
+            }
+        } else {
+            log::warn!("Saving disabled: Failed to find path to data_dir.");
+            None
+        }
+    }
+
+    /// Use `codecs` for [`crate::epi::Storage::codec`].
+    #[inline]
+    pub fn with_codecs(mut self, codecs: crate::epi::Codecs) -> Self {
+        self.codecs = codecs;
+        self
+    }
+
+    /// Keep this many previous versions of the file. The default is [`Self::DEFAULT_BACKUPS`].
+    #[inline]
+    pub fn with_backups(mut self, backups: usize) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    /// What went wrong when loading the file, e.g. that it was damaged and a backup was used.
+    pub fn take_issues(&mut self) -> Vec<StorageIssue> {
+        std::mem::take(&mut self.issues)
+    }
+
+    fn wait_for_save(&mut self) {
+        if let Some(join_handle) = self.last_save_join_handle.take() {
+            profiling::scope!("wait_for_save");
+            join_handle.join().ok();
+        }
+    }
+}
+
+impl Storage for FileStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.kv.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        if self.kv.get(key) != Some(&value) {
+            self.kv.insert(key.to_owned(), value);
+            self.changed.insert(key.to_owned());
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if self.kv.remove(key).is_some() {
+            self.changed.insert(key.to_owned());
+        }
+    }
+
+    fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.kv.keys().cloned().collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    fn codec(&self, key: &str) -> crate::epi::Codec {
+        self.codecs.get(key)
+    }
+
+    fn watch(&mut self, on_change: Box<dyn Fn() + Send + Sync>) -> bool {
+        use notify::Watcher as _;
+
+        // The file is replaced rather than written to, so watch the directory it's in.
+        let (Some(dir), Some(file_name)) =
+            (self.ron_filepath.parent(), self.ron_filepath.file_name())
+        else {
+            return false;
+        };
+        let file_name = file_name.to_owned();
+
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            // We read the file ourselves in `reload`.
+            if !event.kind.is_access()
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == Some(file_name.as_os_str()))
+            {
+                on_change();
+            }
+        });
+        let result = watcher.and_then(|mut watcher| {
+            watcher.watch(dir, notify::RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        });
+        match result {
+            Ok(watcher) => {
+                self.watcher = Some(watcher);
+                true
+            }
+            Err(err) => {
+                log::warn!("Failed to watch {:?} for changes: {err}", self.ron_filepath);
+                false
+            }
+        }
+    }
+
+    fn reload(&mut self) -> Vec<String> {
+        profiling::function_scope!();
+
+        // Our own changes must be on disk before comparing, or it looks like someone reverted them.
+        self.wait_for_save();
+
+        let on_disk = {
+            let _lock = lock(&self.ron_filepath, Lock::Shared);
+            match read_verified(&self.ron_filepath) {
+                Ok(Some(on_disk)) => on_disk,
+                Ok(None) => return Vec::new(),
+                Err(err) => {
+                    // Probably being written by something that doesn't know about the lock.
+                    log::debug!("Failed to reload {:?}: {err}", self.ron_filepath);
+                    return Vec::new();
+                }
+            }
+        };
+
+        let mut keys: Vec<String> = self
+            .kv
+            .keys()
+            .filter(|key| !on_disk.contains_key(*key))
+            .chain(
+                on_disk
+                    .iter()
+                    .filter(|(key, value)| self.kv.get(*key) != Some(value))
+                    .map(|(key, _)| key),
+            )
+            .filter(|key| !self.changed.contains(*key))
+            .cloned()
+            .collect();
+        keys.sort_unstable();
+
+        for key in &keys {
+            match on_disk.get(key) {
+                Some(value) => self.kv.insert(key.clone(), value.clone()),
+                None => self.kv.remove(key),
+            };
+        }
+        keys
+    }
+
+    fn flush(&mut self) {
+        if !self.changed.is_empty() {
+            profiling::function_scope!();
+
+            let file_path = self.ron_filepath.clone();
+            let changes: HashMap<String, Option<String>> = self
+                .changed
+                .drain()
+                .map(|key| {
+                    let value = self.kv.get(&key).cloned();
+                    (key, value)
+                })
+                .collect();
+            let backups = self.backups;
+
+            // wait for previous save to complete.
+            self.wait_for_save();
+
+            let result = std::thread::Builder::new()
+                .name("eframe_persist".to_owned())
+                .spawn(move || {
+                    if let Err(err) = save_to_disk(&file_path, &changes, backups) {
+                        log::warn!("Failed to save app state to {file_path:?}: {err}");
+                    } else {
+                        log::trace!("Persisted to {:?}", file_path);
+                    }
+                });
+            match result {
+                Ok(join_handle) => {
+                    self.last_save_join_handle = Some(join_handle);
+                }
+                Err(err) => {
+                    log::warn!("Failed to spawn thread to save app state: {err}");
+                }
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// The first line of the file, followed by the CRC32 of everything after that line.
+const CHECKSUM_PREFIX: &str = "// crc32: ";
+
+/// `app.ron` -> `app.ron.{suffix}`
+fn sibling(path: &Path, suffix: impl std::fmt::Display) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(format!(".{suffix}"));
+    path.with_file_name(name)
+}
+
+#[derive(Clone, Copy)]
+enum Lock {
+    Shared,
+    Exclusive,
+}
+
+/// Lock `<file>.lock` until the returned file is dropped.
+fn lock(file_path: &Path, lock: Lock) -> std::io::Result<File> {
+    let lock_path = sibling(file_path, "lock");
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+    match lock {
+        Lock::Shared => file.lock_shared()?,
+        Lock::Exclusive => file.lock()?,
+    }
+    Ok(file)
+}
+
+/// Write `changes` (`None` meaning removed) on top of what's in the file now.
+fn save_to_disk(
+    file_path: &Path,
+    changes: &HashMap<String, Option<String>>,
+    backups: usize,
+) -> Result<(), String> {
+    profiling::function_scope!();
+
+    if let Some(parent_dir) = file_path.parent()
+        && !parent_dir.exists()
+    {
+        std::fs::create_dir_all(parent_dir)
+            .map_err(|err| format!("Failed to create directory {parent_dir:?}: {err}"))?;
+    }
+
+    let _lock = lock(file_path, Lock::Exclusive)
+        .map_err(|err| format!("Failed to lock {file_path:?}: {err}"))?;
+
+    // Another instance may have written since we loaded, so only change what we changed.
+    // If the file is damaged, only our changes survive, which is still better than nothing.
+    let on_disk = read_verified(file_path);
+    let file_is_valid = matches!(on_disk, Ok(Some(_)));
+    let mut kv = on_disk.ok().flatten().unwrap_or_default();
+    for (key, value) in changes {
+        match value {
+            Some(value) => kv.insert(key.clone(), value.clone()),
+            None => kv.remove(key),
+        };
+    }
+
+    let body = {
+        profiling::scope!("ron::serialize");
+        ron::ser::to_string_pretty(&kv, Default::default())
+            .map_err(|err| format!("Failed to serialize app state: {err}"))?
+    };
+    let contents = format!(
+        "{CHECKSUM_PREFIX}{:08x}\n{body}",
+        crc32fast::hash(body.as_bytes())
+    );
+
+    // Write everything before touching the old file, so a crash can't leave a half-written file behind.
+    let tmp_path = sibling(file_path, "tmp");
+    let write_tmp = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    };
+    write_tmp().map_err(|err| format!("Failed to write {tmp_path:?}: {err}"))?;
+
+    // A damaged file would push a good backup out, so only keep valid ones.
+    if 0 < backups && file_is_valid {
+        for i in (1..backups).rev() {
+            let from = sibling(file_path, i);
+            if from.exists() {
+                std::fs::rename(&from, sibling(file_path, i + 1)).ok();
+            }
+        }
+        // Copy rather than move, so there is always a file at `file_path`.
+        if let Err(err) = std::fs::copy(file_path, sibling(file_path, 1)) {
+            log::warn!("Failed to back up {file_path:?}: {err}");
+        }
+    }
+
+    std::fs::rename(&tmp_path, file_path)
+        .map_err(|err| format!("Failed to replace {file_path:?}: {err}"))
+}
+
+// ----------------------------------------------------------------------------
+
+/// Load the file, or the newest valid backup if it is damaged.
+fn load(file_path: &Path) -> (HashMap<String, String>, Vec<StorageIssue>) {
+    {
+        // Fails if the directory doesn't exist yet, but then there's nothing to read anyway.
+        let _lock = lock(file_path, Lock::Shared);
+        if let Ok(Some(kv)) = read_verified(file_path) {
+            return (kv, Vec::new());
+        }
+    }
+
+    // Recovering moves the damaged file away, so nobody else may write in the meantime.
+    // Read it again, since another instance may have replaced it after we let go of the lock.
+    let _lock = lock(file_path, Lock::Exclusive);
+    let error = match read_verified(file_path) {
+        Ok(Some(kv)) => return (kv, Vec::new()),
+        // We replace the file by renaming, so a missing file wasn't damaged by a write:
+        // this is the first run, or it was deleted to reset the app.
+        Ok(None) => return (Default::default(), Vec::new()),
+        Err(err) => err,
+    };
+
+    // Keep it, in case nothing else can be recovered and someone wants to have a look.
+    let moved_to = sibling(file_path, "corrupt");
+    let moved_to = std::fs::rename(file_path, &moved_to)
+        .is_ok()
+        .then_some(moved_to);
+
+    for backup in (1..).map(|i| sibling(file_path, i)) {
+        if !backup.exists() {
+            break;
+        }
+        match read_verified(&backup) {
+            Ok(Some(kv)) => {
+                let issue = StorageIssue::RecoveredFromBackup {
+                    path: file_path.to_owned(),
+                    backup,
+                    error,
+                };
+                log::warn!("{issue}");
+                return (kv, vec![issue]);
+            }
+            Ok(None) => break,
+            Err(err) => log::debug!("Backup {backup:?} is damaged too: {err}"),
+        }
+    }
+
+    let issue = StorageIssue::Corrupt {
+        path: file_path.to_owned(),
+        moved_to,
+        error,
+    };
+    log::warn!("{issue}");
+    (Default::default(), vec![issue])
+}
+
+/// Read and check the file. `Ok(None)` if there is no file.
+fn read_verified(file_path: &Path) -> Result<Option<HashMap<String, String>>, String> {
+    profiling::function_scope!();
+    let text = match std::fs::read_to_string(file_path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.to_string()),
+    };
+
+    // Files without a checksum were written before we had them, or edited by hand.
+    let body = if let Some(rest) = text.strip_prefix(CHECKSUM_PREFIX) {
+        let (checksum, body) = rest
+            .split_once('\n')
+            .ok_or("the checksum line is cut off")?;
+        let checksum = u32::from_str_radix(checksum.trim(), 16)
+            .map_err(|err| format!("invalid checksum: {err}"))?;
+        if crc32fast::hash(body.as_bytes()) != checksum {
+            return Err("the checksum doesn't match".to_owned());
+        }
+        body
+    } else {
+        text.as_str()
+    };
+
+    ron::from_str(body)
+        .map(Some)
+        .map_err(|err| format!("Failed to parse RON: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `values` with a fresh storage, and wait for it to hit the disk.
+    fn save(path: &Path, values: &[(&str, &str)]) {
+        let mut storage = FileStorage::from_ron_filepath(path);
+        for (key, value) in values {
+            storage.set_string(key, (*value).to_owned());
+        }
+        storage.flush();
+    }
+
+    fn load(path: &Path) -> (FileStorage, Vec<StorageIssue>) {
+        let mut storage = FileStorage::from_ron_filepath(path);
+        let issues = storage.take_issues();
+        (storage, issues)
+    }
+
+    fn damage(path: &Path, damage: impl FnOnce(&mut Vec<u8>)) {
+        let mut bytes = std::fs::read(path).unwrap();
+        damage(&mut bytes);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn round_trip_without_issues() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        save(&path, &[("a", "1")]);
+
+        let (storage, issues) = load(&path);
+        assert_eq!(storage.get_string("a").as_deref(), Some("1"));
+        assert_eq!(issues, []);
+        assert!(!sibling(&path, "tmp").exists());
+    }
+
+    #[test]
+    fn keeps_rotating_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        for n in 1..=5 {
+            save(&path, &[("n", &n.to_string())]);
+        }
+
+        let n_in = |path: &Path| {
+            let (storage, _) = load(path);
+            storage.get_string("n")
+        };
+        assert_eq!(n_in(&path).as_deref(), Some("5"));
+        assert_eq!(n_in(&sibling(&path, 1)).as_deref(), Some("4"));
+        assert_eq!(n_in(&sibling(&path, 3)).as_deref(), Some("2"));
+        assert!(!sibling(&path, FileStorage::DEFAULT_BACKUPS + 1).exists());
+    }
+
+    #[test]
+    fn truncated_file_recovers_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        save(&path, &[("a", "old")]);
+        save(&path, &[("a", "new")]);
+        damage(&path, |bytes| bytes.truncate(bytes.len() / 2));
+
+        let (storage, issues) = load(&path);
+        assert_eq!(storage.get_string("a").as_deref(), Some("old"));
+        assert!(
+            matches!(&issues[..], [StorageIssue::RecoveredFromBackup { backup, .. }] if *backup == sibling(&path, 1)),
+            "{issues:?}"
+        );
+        assert!(sibling(&path, "corrupt").exists());
+    }
+
+    #[test]
+    fn garbled_file_recovers_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        save(&path, &[("a", "old")]);
+        save(&path, &[("a", "new"), ("b", "2")]);
+
+        // Still valid RON, so only the checksum can tell.
+        damage(&path, |bytes| {
+            let i = bytes.windows(3).position(|w| w == b"new").unwrap();
+            bytes[i..i + 3].copy_from_slice(b"wen");
+        });
+
+        let (storage, issues) = load(&path);
+        assert_eq!(storage.get_string("a").as_deref(), Some("old"));
+        assert_eq!(storage.get_string("b"), None);
+        assert!(
+            matches!(&issues[..], [StorageIssue::RecoveredFromBackup { .. }]),
+            "{issues:?}"
+        );
+    }
+
+    #[test]
+    fn skips_damaged_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        for n in 1..=3 {
+            save(&path, &[("n", &n.to_string())]);
+        }
+        damage(&path, |bytes| bytes.clear());
+        damage(&sibling(&path, 1), |bytes| bytes[3] ^= 0xff);
+
+        let (storage, issues) = load(&path);
+        assert_eq!(storage.get_string("n").as_deref(), Some("1"));
+        assert!(
+            matches!(&issues[..], [StorageIssue::RecoveredFromBackup { backup, .. }] if *backup == sibling(&path, 2)),
+            "{issues:?}"
+        );
+    }
+
+    #[test]
+    fn damaged_without_backup_starts_fresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        save(&path, &[("a", "1")]);
+        damage(&path, |bytes| bytes.truncate(bytes.len() - 4));
+
+        let (storage, issues) = load(&path);
+        assert_eq!(storage.keys(), Vec::<String>::new());
+        let corrupt = sibling(&path, "corrupt");
+        assert!(
+            matches!(&issues[..], [StorageIssue::Corrupt { moved_to: Some(moved_to), .. }] if *moved_to == corrupt),
+            "{issues:?}"
+        );
+        assert!(corrupt.exists());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn deleted_file_is_not_restored_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        save(&path, &[("key", "1")]);
+        save(&path, &[("key", "2")]);
+        std::fs::remove_file(&path).unwrap();
+
+        let (storage, issues) = load(&path);
+        assert_eq!(issues, []);
+        assert_eq!(storage.get_string("key"), None);
+    }
+
+    #[test]
+    fn flush_after_recovery_keeps_the_recovered_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        save(&path, &[("keep", "1"), ("change", "old")]);
+        save(&path, &[("keep", "1"), ("change", "newer")]);
+        damage(&path, |bytes| bytes.truncate(bytes.len() / 2));
+
+        let (mut storage, issues) = load(&path);
+        assert!(
+            matches!(&issues[..], [StorageIssue::RecoveredFromBackup { .. }]),
+            "{issues:?}"
+        );
+        storage.set_string("change", "new".to_owned());
+        storage.flush();
+        drop(storage);
+
+        let (storage, issues) = load(&path);
+        assert_eq!(issues, []);
+        assert_eq!(storage.get_string("keep").as_deref(), Some("1"));
+        assert_eq!(storage.get_string("change").as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn accepts_files_without_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        std::fs::write(&path, r#"{"a": "hand-edited"}"#).unwrap();
+
+        let (storage, issues) = load(&path);
+        assert_eq!(storage.get_string("a").as_deref(), Some("hand-edited"));
+        assert_eq!(issues, []);
+    }
+
+    #[test]
+    fn flush_merges_with_other_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        save(&path, &[("a", "0"), ("b", "0"), ("c", "0")]);
+
+        let mut first = FileStorage::from_ron_filepath(&path);
+        let mut second = FileStorage::from_ron_filepath(&path);
+        first.set_string("a", "first".to_owned());
+        first.set_string("b", "first".to_owned());
+        second.set_string("b", "second".to_owned());
+        second.remove("c");
+        first.flush();
+        first.wait_for_save();
+        second.flush();
+        second.wait_for_save();
+
+        let (storage, _) = load(&path);
+        assert_eq!(storage.get_string("a").as_deref(), Some("first"));
+        assert_eq!(storage.get_string("b").as_deref(), Some("second"));
+        assert_eq!(storage.get_string("c"), None);
+    }
+
+    #[test]
+    fn reload_picks_up_changes_from_outside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        save(&path, &[("theme", "dark"), ("font", "12"), ("mine", "0")]);
+
+        let mut storage = FileStorage::from_ron_filepath(&path);
+        storage.set_string("mine", "unsaved".to_owned());
+
+        save(
+            &path,
+            &[("theme", "light"), ("mine", "theirs"), ("new", "1")],
+        );
+        let mut other = FileStorage::from_ron_filepath(&path);
+        other.remove("font");
+        other.flush();
+        drop(other);
+
+        assert_eq!(storage.reload(), ["font", "new", "theme"]);
+        assert_eq!(storage.get_string("theme").as_deref(), Some("light"));
+        assert_eq!(storage.get_string("font"), None);
+        assert_eq!(storage.get_string("mine").as_deref(), Some("unsaved"));
+        assert_eq!(storage.reload(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn watch_notices_other_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ron");
+        save(&path, &[("a", "1")]);
+
+        let mut storage = FileStorage::from_ron_filepath(&path);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = std::sync::Mutex::new(tx);
+        assert!(storage.watch(Box::new(move || {
+            tx.lock().unwrap().send(()).ok();
+        })));
+
+        save(&path, &[("a", "2")]);
+        rx.recv_timeout(std::time::Duration::from_secs(10))
+            .expect("no change notification");
+        assert_eq!(storage.reload(), ["a"]);
+    }
+}
@@ -0,0 +1,134 @@
+//! Immediate viewport renderers scoped to a single [`egui::Context`].
+//!
+//! [`egui::Context::set_immediate_viewport_renderer`] only holds one renderer per thread, so two apps
+//! on the same thread, e.g. one run after the other, would end up rendering each other's immediate viewports.
+//! Instead we install one dispatching renderer and look up the renderer registered
+//! for the [`egui::Context`] that is showing the viewport.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use egui::ImmediateViewport;
+
+type Renderer = dyn Fn(&egui::Context, ImmediateViewport<'_>);
+
+thread_local! {
+    static RENDERERS: RefCell<Vec<(egui::Context, Rc<Renderer>)>> = const { RefCell::new(Vec::new()) };
+    static DISPATCHER_INSTALLED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Unregisters the renderer of a context when dropped.
+#[must_use = "The renderer is unregistered when the guard is dropped"]
+pub struct RendererGuard {
+    egui_ctx: egui::Context,
+}
+
+impl Drop for RendererGuard {
+    fn drop(&mut self) {
+        RENDERERS.with(|renderers| {
+            renderers
+                .borrow_mut()
+                .retain(|(egui_ctx, _)| egui_ctx != &self.egui_ctx);
+        });
+    }
+}
+
+/// Render the immediate viewports of `egui_ctx` with `renderer`, until the guard is dropped.
+///
+/// Replaces any renderer previously registered for the same context.
+pub fn register(
+    egui_ctx: &egui::Context,
+    renderer: impl Fn(&egui::Context, ImmediateViewport<'_>) + 'static,
+) -> RendererGuard {
+    if !DISPATCHER_INSTALLED.replace(true) {
+        egui::Context::set_immediate_viewport_renderer(dispatch);
+    }
+
+    RENDERERS.with(|renderers| {
+        let mut renderers = renderers.borrow_mut();
+        renderers.retain(|(ctx, _)| ctx != egui_ctx);
+        renderers.push((egui_ctx.clone(), Rc::new(renderer)));
+    });
+
+    RendererGuard {
+        egui_ctx: egui_ctx.clone(),
+    }
+}
+
+fn dispatch(egui_ctx: &egui::Context, immediate_viewport: ImmediateViewport<'_>) {
+    // Don't hold the borrow while rendering: immediate viewports can be nested.
+    let renderer = RENDERERS.with(|renderers| {
+        renderers
+            .borrow()
+            .iter()
+            .find(|(ctx, _)| ctx == egui_ctx)
+            .map(|(_, renderer)| renderer.clone())
+    });
+
+    if let Some(renderer) = renderer {
+        renderer(egui_ctx, immediate_viewport);
+    } else {
+        log::warn!("No immediate viewport renderer registered for this egui::Context");
+        // egui expects the UI to run either way.
+        let mut viewport_ui_cb = immediate_viewport.viewport_ui_cb;
+        viewport_ui_cb(egui_ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Show an immediate viewport from `egui_ctx`, as an app would.
+    fn show_immediate(egui_ctx: &egui::Context) {
+        egui_ctx.set_embed_viewports(false);
+        let _ = egui_ctx.run(egui::RawInput::default(), |ctx| {
+            ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of("immediate"),
+                egui::ViewportBuilder::default(),
+                |_, _| {},
+            );
+        });
+    }
+
+    fn counting_renderer(
+        count: &Rc<Cell<usize>>,
+    ) -> impl Fn(&egui::Context, ImmediateViewport<'_>) + 'static {
+        let count = count.clone();
+        move |egui_ctx, mut immediate_viewport| {
+            count.set(count.get() + 1);
+            (immediate_viewport.viewport_ui_cb)(egui_ctx);
+        }
+    }
+
+    #[test]
+    fn renders_with_the_renderer_of_the_context() {
+        let [a, b] = [egui::Context::default(), egui::Context::default()];
+        let [a_count, b_count] = [Rc::new(Cell::new(0)), Rc::new(Cell::new(0))];
+        let _a_guard = register(&a, counting_renderer(&a_count));
+        let b_guard = register(&b, counting_renderer(&b_count));
+
+        show_immediate(&a);
+        assert_eq!((a_count.get(), b_count.get()), (1, 0));
+        show_immediate(&b);
+        assert_eq!((a_count.get(), b_count.get()), (1, 1));
+
+        drop(b_guard);
+        show_immediate(&b);
+        show_immediate(&a);
+        assert_eq!((a_count.get(), b_count.get()), (2, 1));
+    }
+
+    #[test]
+    fn registering_again_replaces_the_renderer() {
+        let egui_ctx = egui::Context::default();
+        let [first, second] = [Rc::new(Cell::new(0)), Rc::new(Cell::new(0))];
+        let _first_guard = register(&egui_ctx, counting_renderer(&first));
+        let _second_guard = register(&egui_ctx, counting_renderer(&second));
+
+        show_immediate(&egui_ctx);
+        assert_eq!((first.get(), second.get()), (0, 1));
+    }
+}
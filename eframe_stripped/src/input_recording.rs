@@ -0,0 +1,181 @@
+//! Recording the [`egui::RawInput`] of every frame, and replaying it later.
+//!
+//! Recordings are JSON lines, one [`egui::RawInput`] per frame of the root viewport,
+//! as it was before [`crate::epi::App::raw_input_hook`] was called.
+//! Immediate viewports are not recorded.
+//!
+//! See [`crate::NativeOptions::record_input`] and [`crate::NativeOptions::replay_input`].
+
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead as _, BufReader, LineWriter, Write as _},
+    path::{Path, PathBuf},
+};
+
+use crate::epi;
+
+/// Writes each frame's input to a file.
+pub struct InputRecorder {
+    path: PathBuf,
+    writer: LineWriter<File>,
+}
+
+impl InputRecorder {
+    pub fn new(path: &Path) -> std::io::Result<Self> {
+        log::info!("Recording input to {}", path.display());
+        Ok(Self {
+            path: path.to_owned(),
+            writer: LineWriter::new(File::create(path)?),
+        })
+    }
+
+    pub fn record(&mut self, raw_input: &egui::RawInput) {
+        profiling::function_scope!();
+        let result = serde_json::to_writer(&mut self.writer, raw_input)
+            .map_err(std::io::Error::from)
+            .and_then(|()| self.writer.write_all(b"\n"));
+        if let Err(err) = result {
+            log::warn!("Failed to record input to {}: {err}", self.path.display());
+        }
+    }
+}
+
+/// Recorded frames still to be replayed.
+pub struct InputReplay {
+    frames: VecDeque<egui::RawInput>,
+}
+
+impl InputReplay {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let mut frames = VecDeque::new();
+        for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let raw_input = serde_json::from_str(&line).map_err(|err| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}:{}: {err}", path.display(), i + 1),
+                )
+            })?;
+            frames.push_back(raw_input);
+        }
+        log::info!(
+            "Replaying {} frames of input from {}",
+            frames.len(),
+            path.display()
+        );
+        Ok(Self { frames })
+    }
+
+    /// The input of the next recorded frame, or `None` when the recording is over.
+    pub fn next_frame(&mut self) -> Option<egui::RawInput> {
+        self.frames.pop_front()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+/// Run the app on the recorded input without creating any window, until the recording is over.
+///
+/// Nothing is painted: this only calls [`epi::App::update`], so there is no GL context
+/// and [`epi::Frame::gl`] is `None`.
+pub fn replay_headless(path: &Path, app_creator: epi::AppCreator<'_>) -> Result<(), crate::Error> {
+    let mut replay = InputReplay::load(path).map_err(crate::Error::InputRecording)?;
+
+    let egui_ctx = egui::Context::default();
+    let mut app = app_creator(&epi::CreationContext::_new_kittest(egui_ctx.clone()))
+        .map_err(crate::Error::AppCreation)?;
+    let mut frame = epi::Frame::_new_kittest();
+
+    while let Some(mut raw_input) = replay.next_frame() {
+        app.raw_input_hook(&egui_ctx, &mut raw_input);
+        // The output only matters for painting, which we don't do.
+        let _ = egui_ctx.run(raw_input, |egui_ctx| app.update(egui_ctx, &mut frame));
+    }
+
+    #[cfg(feature = "glow")]
+    app.on_exit(None);
+    #[cfg(not(feature = "glow"))]
+    app.on_exit();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames() -> Vec<egui::RawInput> {
+        let root = egui::ViewportId::ROOT;
+        let mut first = egui::RawInput {
+            time: Some(1.5),
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(800.0, 600.0),
+            )),
+            events: vec![
+                egui::Event::PointerMoved(egui::pos2(10.25, 20.0)),
+                egui::Event::Text("héllo".to_owned()),
+            ],
+            ..Default::default()
+        };
+        first
+            .viewports
+            .entry(root)
+            .or_default()
+            .native_pixels_per_point = Some(2.0);
+        let second = egui::RawInput {
+            time: Some(1.5 + 1.0 / 60.0),
+            events: vec![egui::Event::Key {
+                key: egui::Key::Enter,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: egui::Modifiers::CTRL,
+            }],
+            ..Default::default()
+        };
+        vec![first, second]
+    }
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.jsonl");
+
+        let mut recorder = InputRecorder::new(&path).unwrap();
+        for raw_input in frames() {
+            recorder.record(&raw_input);
+        }
+        drop(recorder);
+
+        let mut replay = InputReplay::load(&path).unwrap();
+        for expected in frames() {
+            assert!(!replay.is_finished());
+            assert_eq!(replay.next_frame(), Some(expected));
+        }
+        assert!(replay.is_finished());
+        assert_eq!(replay.next_frame(), None);
+    }
+
+    #[test]
+    fn malformed_line_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.jsonl");
+        let valid = serde_json::to_string(&egui::RawInput::default()).unwrap();
+        std::fs::write(&path, format!("{valid}\n\n{{\"time\": \n")).unwrap();
+
+        let err = InputReplay::load(&path).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("input.jsonl:3:"), "{err}");
+
+        let missing = InputReplay::load(&dir.path().join("missing.jsonl"))
+            .err()
+            .unwrap();
+        assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+    }
+}
@@ -1,9 +1,25 @@
 mod app_icon;
+#[cfg(feature = "ron")]
+mod codec;
+mod debug_overlay;
 pub mod epi;
 mod epi_native;
 mod event_loop_context;
+mod event_trace;
+#[cfg(feature = "persistence")]
+mod file_storage;
+mod immediate_viewport;
+#[cfg(feature = "input_recording")]
+mod input_recording;
+mod migration;
+mod monitor;
+#[cfg(unix)]
+mod single_instance;
 mod stopwatch;
+mod storage;
 mod stuff;
+pub mod testing;
+mod texture_mirror;
 mod winit_app;
 mod winit_integration;
 mod winit_wrapper;
@@ -17,16 +33,79 @@ use winit_app::GlowWinitApp;
 use winit_integration::WinitApp;
 use winit_wrapper::WinitAppWrapper;
 
+/// Run an [`epi::App`] on the current thread until its root window is closed.
+///
+/// Each call gets its own [`egui::Context`], GL display and immediate viewport renderer.
+/// egui keeps one immediate viewport renderer per thread
+/// (see [`egui::Context::set_immediate_viewport_renderer`]), so eframe installs one that
+/// hands each viewport to the renderer of the [`egui::Context`] showing it.
+///
+/// Running two apps at once on different threads isn't supported, though:
+/// [`winit`] only allows one [`EventLoop`] per process. The event loop is kept in a thread-local
+/// so it can be reused by later calls on the same thread, which means calls from any other thread
+/// will fail with [`Error::WinitEventLoop`].
+///
+/// With [`NativeOptions::single_instance`], this returns [`RunOutcome::AlreadyRunning`]
+/// right away if the app is already running.
 pub fn run_native(
     app_name: &str,
     mut native_options: NativeOptions,
     app_creator: AppCreator<'_>,
-) -> Result<(), crate::Error> {
-    return with_event_loop(native_options, |event_loop, native_options| {
+) -> Result<RunOutcome, crate::Error> {
+    #[cfg(feature = "input_recording")]
+    if native_options.replay_headless
+        && let Some(path) = &native_options.replay_input
+    {
+        input_recording::replay_headless(path, app_creator)?;
+        return Ok(RunOutcome::Exited);
+    }
+
+    #[cfg(unix)]
+    let single_instance = if native_options.single_instance {
+        use single_instance::{Acquired, SingleInstance};
+
+        let app_id = native_options
+            .viewport
+            .app_id
+            .as_deref()
+            .unwrap_or(app_name);
+        let args: Vec<_> = std::env::args_os().skip(1).collect();
+        match SingleInstance::acquire(app_id, &args) {
+            Ok(Acquired::AlreadyRunning) => return Ok(RunOutcome::AlreadyRunning),
+            Ok(Acquired::First(single_instance)) => Some(single_instance),
+            Err(err) => {
+                log::warn!("Failed to check for other instances, running anyway: {err}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    #[cfg(not(unix))]
+    if native_options.single_instance {
+        log::warn!("NativeOptions::single_instance is only supported on Unix");
+    }
+
+    with_event_loop(native_options, |event_loop, native_options| {
         // HERE
+        let trace = event_trace::EventTrace::new(native_options.event_trace_path.as_deref());
         let glow_eframe = GlowWinitApp::new(event_loop, app_name, native_options, app_creator);
-        run_and_return(event_loop, glow_eframe)
-    })?;
+        #[cfg(unix)]
+        let glow_eframe = glow_eframe.with_single_instance(single_instance);
+        run_and_return(event_loop, glow_eframe, trace)
+    })??;
+    Ok(RunOutcome::Exited)
+}
+
+/// How [`run_native`] ended, if it didn't fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// The app ran until its root window was closed.
+    Exited,
+
+    /// [`NativeOptions::single_instance`] is set and the app was already running,
+    /// so the command-line arguments were forwarded to it and nothing else happened.
+    AlreadyRunning,
 }
 
 /// Access a thread-local event loop.
@@ -74,12 +153,13 @@ fn create_event_loop(
 fn run_and_return(
     event_loop: &mut EventLoop<UserEvent>,
     winit_app: impl WinitApp,
+    trace: Option<event_trace::EventTrace>,
 ) -> Result<(), crate::Error> {
     use winit::platform::run_on_demand::EventLoopExtRunOnDemand as _;
 
     log::trace!("Entering the winit event loop (run_app_on_demand)…");
 
-    let mut app = WinitAppWrapper::new(winit_app, true);
+    let mut app = WinitAppWrapper::new(winit_app, true, trace);
     event_loop.run_app_on_demand(&mut app)?;
     log::debug!("eframe window closed");
     app.return_result
@@ -91,6 +171,11 @@ pub enum Error {
     /// Something went wrong in user code when creating the app.
     AppCreation(Box<dyn std::error::Error + Send + Sync>),
 
+    /// User code (e.g. [`epi::App::update`] or the [`AppCreator`]) panicked.
+    ///
+    /// Contains the panic message. All windows have been closed, and [`run_native`] can be called again.
+    AppPanicked(String),
+
     /// An error from [`winit`].
     #[cfg(not(target_arch = "wasm32"))]
     Winit(winit::error::OsError),
@@ -104,15 +189,139 @@ pub enum Error {
     Glutin(glutin::error::Error),
 
     /// An error from [`glutin`] when using [`glow`].
+    ///
+    /// Holds the [`glutin::config::ConfigTemplate`] formatted with [`std::fmt::Debug`],
+    /// as the template itself may contain a (non-[`Send`]) window handle.
     #[cfg(all(feature = "glow", not(target_arch = "wasm32")))]
-    NoGlutinConfigs(glutin::config::ConfigTemplate, Box<dyn std::error::Error>),
+    NoGlutinConfigs(String, Box<dyn std::error::Error + Send + Sync>),
 
     /// An error from [`glutin`] when using [`glow`].
     #[cfg(feature = "glow")]
     OpenGL(egui_glow::PainterError),
+
+    /// The file given in [`NativeOptions::record_input`] or [`NativeOptions::replay_input`]
+    /// couldn't be created or read.
+    #[cfg(feature = "input_recording")]
+    InputRecording(std::io::Error),
 }
 
-impl std::error::Error for Error {}
+// The spawned-thread mode hands errors back across the thread boundary.
+static_assertions::assert_impl_all!(Error: Send, Sync);
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::AppCreation(err) => Some(err.as_ref()),
+
+            Self::AppPanicked(_) => None,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Winit(err) => Some(err),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::WinitEventLoop(err) => Some(err),
+
+            #[cfg(all(feature = "glow", not(target_arch = "wasm32")))]
+            Self::Glutin(err) => Some(err),
+
+            #[cfg(all(feature = "glow", not(target_arch = "wasm32")))]
+            Self::NoGlutinConfigs(_, err) => Some(err.as_ref()),
+
+            #[cfg(feature = "glow")]
+            Self::OpenGL(err) => Some(err),
+
+            #[cfg(feature = "input_recording")]
+            Self::InputRecording(err) => Some(err),
+        }
+    }
+}
+
+impl Error {
+    /// What category of problem this is, e.g. to decide whether to retry with other [`NativeOptions`].
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::AppCreation(_) => ErrorKind::AppCreation,
+
+            Self::AppPanicked(_) => ErrorKind::AppPanicked,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Winit(_) => ErrorKind::Other,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::WinitEventLoop(err) => match err {
+                winit::error::EventLoopError::RecreationAttempt => ErrorKind::EventLoopReuse,
+                winit::error::EventLoopError::NotSupported(_)
+                | winit::error::EventLoopError::Os(_) => ErrorKind::NoDisplay,
+                winit::error::EventLoopError::ExitFailure(_) => ErrorKind::Other,
+            },
+
+            #[cfg(all(feature = "glow", not(target_arch = "wasm32")))]
+            Self::Glutin(err) => match err.error_kind() {
+                glutin::error::ErrorKind::ContextLost
+                | glutin::error::ErrorKind::BadContext
+                | glutin::error::ErrorKind::BadContextState
+                | glutin::error::ErrorKind::BadCurrentSurface
+                | glutin::error::ErrorKind::BadSurface => ErrorKind::ContextLost,
+                glutin::error::ErrorKind::NotFound | glutin::error::ErrorKind::BadDisplay => {
+                    ErrorKind::NoDisplay
+                }
+                glutin::error::ErrorKind::BadConfig => ErrorKind::NoGlConfig,
+                _ => ErrorKind::Other,
+            },
+
+            #[cfg(all(feature = "glow", not(target_arch = "wasm32")))]
+            Self::NoGlutinConfigs(..) => ErrorKind::NoGlConfig,
+
+            #[cfg(feature = "glow")]
+            Self::OpenGL(_) => ErrorKind::Other,
+
+            #[cfg(feature = "input_recording")]
+            Self::InputRecording(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// The category of an [`Error`], see [`Error::kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// No display server could be connected to, e.g. neither `WAYLAND_DISPLAY` nor `DISPLAY` is set.
+    NoDisplay,
+
+    /// No OpenGL config matched the requested [`NativeOptions`].
+    ///
+    /// Retrying with e.g. a different [`epi::HardwareAcceleration`] or less multisampling may help.
+    NoGlConfig,
+
+    /// The OpenGL context was lost or became unusable, e.g. after a GPU reset.
+    ContextLost,
+
+    /// The [`AppCreator`] returned an error.
+    AppCreation,
+
+    /// User code panicked.
+    AppPanicked,
+
+    /// The event loop could not be (re-)created, e.g. because one already exists on another thread.
+    EventLoopReuse,
+
+    /// Anything else.
+    Other,
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoDisplay => "no display",
+            Self::NoGlConfig => "no matching OpenGL config",
+            Self::ContextLost => "OpenGL context lost",
+            Self::AppCreation => "app creation failed",
+            Self::AppPanicked => "app panicked",
+            Self::EventLoopReuse => "event loop can't be reused",
+            Self::Other => "other",
+        }
+        .fmt(f)
+    }
+}
 
 #[cfg(not(target_arch = "wasm32"))]
 impl From<winit::error::OsError> for Error {
@@ -151,6 +360,8 @@ impl std::fmt::Display for Error {
         match self {
             Self::AppCreation(err) => write!(f, "app creation error: {err}"),
 
+            Self::AppPanicked(message) => write!(f, "app panicked: {message}"),
+
             #[cfg(not(target_arch = "wasm32"))]
             Self::Winit(err) => {
                 write!(f, "winit error: {err}")
@@ -170,7 +381,7 @@ impl std::fmt::Display for Error {
             Self::NoGlutinConfigs(template, err) => {
                 write!(
                     f,
-                    "Found no glutin configs matching the template: {template:?}. Error: {err}"
+                    "Found no glutin configs matching the template: {template}. Error: {err}"
                 )
             }
 
@@ -178,6 +389,11 @@ impl std::fmt::Display for Error {
             Self::OpenGL(err) => {
                 write!(f, "egui_glow: {err}")
             }
+
+            #[cfg(feature = "input_recording")]
+            Self::InputRecording(err) => {
+                write!(f, "input recording: {err}")
+            }
         }
     }
 }
@@ -0,0 +1,445 @@
+//! Versioning of persisted state, so a format change doesn't silently reset it.
+//!
+//! The version a value was written with is stored next to it, at `<key>.version`.
+//! Values that can't be decoded or migrated are moved to `<key>.backup` (and their version to
+//! `<key>.backup.version`), and reported through [`crate::epi::CreationContext::storage_issues`].
+
+use std::{collections::BTreeMap, sync::Arc};
+
+#[cfg(feature = "persistence")]
+use crate::epi::Storage;
+
+/// The version stored next to egui's memory and the window settings of all viewports.
+///
+/// Their format is defined by egui and egui-winit, so this follows the egui version in `Cargo.toml`.
+#[cfg(feature = "persistence")]
+pub const EGUI_STATE_VERSION: &str = "egui-0.31";
+
+/// The key where the version of the value at `key` is stored.
+#[cfg(feature = "persistence")]
+pub fn version_key(key: &str) -> String {
+    format!("{key}.version")
+}
+
+/// The key where a value at `key` that couldn't be restored is kept.
+#[cfg(feature = "persistence")]
+pub fn backup_key(key: &str) -> String {
+    format!("{key}.backup")
+}
+
+// ----------------------------------------------------------------------------
+
+/// Something that went wrong when loading or restoring persisted state.
+///
+/// See [`crate::epi::CreationContext::storage_issues`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StorageIssue {
+    /// The value could not be decoded or migrated, so it was moved to `backup_key`
+    /// and the default is used instead.
+    Undecodable {
+        key: String,
+
+        /// The version the value was written with, if one was stored.
+        stored_version: Option<String>,
+
+        /// The version we expected.
+        current_version: String,
+
+        /// Where the value was moved to.
+        backup_key: String,
+
+        error: String,
+    },
+
+    /// The storage file was damaged, e.g. by a crash while writing it, so the newest valid backup was loaded instead.
+    RecoveredFromBackup {
+        path: std::path::PathBuf,
+        backup: std::path::PathBuf,
+        error: String,
+    },
+
+    /// The storage file was damaged and there was no valid backup, so everything starts out fresh.
+    Corrupt {
+        path: std::path::PathBuf,
+
+        /// Where the damaged file was moved to, if that worked.
+        moved_to: Option<std::path::PathBuf>,
+
+        error: String,
+    },
+}
+
+impl std::fmt::Display for StorageIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Undecodable {
+                key,
+                stored_version,
+                current_version,
+                backup_key,
+                error,
+            } => write!(
+                f,
+                "Failed to restore {key:?} (version {}, expected {current_version}): {error}. It was moved to {backup_key:?}.",
+                stored_version.as_deref().unwrap_or("unknown")
+            ),
+            Self::RecoveredFromBackup {
+                path,
+                backup,
+                error,
+            } => write!(
+                f,
+                "{path:?} is damaged ({error}), so the backup {backup:?} was loaded instead."
+            ),
+            Self::Corrupt {
+                path,
+                moved_to,
+                error,
+            } => {
+                write!(
+                    f,
+                    "{path:?} is damaged ({error}) and there is no valid backup."
+                )?;
+                if let Some(moved_to) = moved_to {
+                    write!(f, " It was moved to {moved_to:?}.")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Turns one version of the encoded app state into the next version.
+///
+/// The app state is encoded with the [`crate::epi::Codec`] the storage uses for [`crate::epi::APP_KEY`],
+/// which is RON unless configured otherwise.
+pub type Migration = Arc<dyn Fn(String) -> Result<String, String> + Send + Sync>;
+
+/// The version of the app state stored at [`crate::epi::APP_KEY`], and how to migrate older versions to it.
+///
+/// Migrations are applied before the app is created, so [`crate::epi::CreationContext::storage`]
+/// always contains the current version.
+/// Values stored without a version are version 0.
+///
+/// ```
+/// use eframe_stripped::epi::AppMigrations;
+///
+/// // Version 1 renamed `name` to `title`. The app state is stored as RON.
+/// let migrations = AppMigrations::new(1)
+///     .with_migration(0, |value| Ok(value.replace("name:", "title:")));
+///
+/// assert_eq!(
+///     migrations.migrate("(name: \"a\")".to_owned(), 0),
+///     Ok("(title: \"a\")".to_owned())
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct AppMigrations {
+    version: u32,
+    migrations: BTreeMap<u32, Migration>,
+}
+
+impl std::fmt::Debug for AppMigrations {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppMigrations")
+            .field("version", &self.version)
+            .field("from_versions", &self.migrations.keys())
+            .finish()
+    }
+}
+
+impl AppMigrations {
+    /// `version` is the version of the app state the app currently writes.
+    pub fn new(version: u32) -> Self {
+        Self {
+            version,
+            migrations: Default::default(),
+        }
+    }
+
+    /// Register how to turn version `from` into version `from + 1`.
+    #[inline]
+    pub fn with_migration(
+        mut self,
+        from: u32,
+        migration: impl Fn(String) -> Result<String, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.migrations.insert(from, Arc::new(migration));
+        self
+    }
+
+    /// The version of the app state the app currently writes.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Run all migrations needed to turn `value` of `stored_version` into the current version.
+    pub fn migrate(&self, mut value: String, stored_version: u32) -> Result<String, String> {
+        if self.version < stored_version {
+            return Err(format!(
+                "version {stored_version} is newer than this app's version {}",
+                self.version
+            ));
+        }
+        for from in stored_version..self.version {
+            let migration = self
+                .migrations
+                .get(&from)
+                .ok_or_else(|| format!("no migration from version {from}"))?;
+            value = migration(value)
+                .map_err(|err| format!("migration from version {from} failed: {err}"))?;
+        }
+        Ok(value)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Make sure everything at the versioned keys can be restored, before anything reads from `storage`.
+///
+/// egui's memory and the window settings are checked by decoding them if they were written with
+/// another version, and the app state is migrated.
+/// Anything that can't be restored is backed up and returned.
+#[cfg(feature = "persistence")]
+pub(crate) fn prepare_storage(
+    storage: &mut dyn Storage,
+    app_migrations: &AppMigrations,
+) -> Vec<StorageIssue> {
+    use crate::epi_native::{
+        PersistedViewport, STORAGE_EGUI_MEMORY_KEY, STORAGE_VIEWPORTS_KEY, STORAGE_WINDOW_KEY,
+        STORAGE_WINDOW_PLACEMENT_KEY,
+    };
+
+    profiling::function_scope!();
+    let mut issues = Vec::new();
+    issues.extend(check_decodes::<egui::Memory>(
+        storage,
+        STORAGE_EGUI_MEMORY_KEY,
+    ));
+    issues.extend(check_decodes::<egui_winit::WindowSettings>(
+        storage,
+        STORAGE_WINDOW_KEY,
+    ));
+    issues.extend(check_decodes::<crate::monitor::MonitorPlacement>(
+        storage,
+        STORAGE_WINDOW_PLACEMENT_KEY,
+    ));
+    issues.extend(check_decodes::<Vec<PersistedViewport>>(
+        storage,
+        STORAGE_VIEWPORTS_KEY,
+    ));
+    issues.extend(migrate_app_state(storage, app_migrations));
+
+    for issue in &issues {
+        log::warn!("{issue}");
+    }
+    issues
+}
+
+#[cfg(feature = "persistence")]
+fn check_decodes<T: serde::de::DeserializeOwned>(
+    storage: &mut dyn Storage,
+    key: &str,
+) -> Option<StorageIssue> {
+    let value = storage.get_string(key)?;
+    let stored_version = storage.get_string(&version_key(key));
+    if stored_version.as_deref() != Some(EGUI_STATE_VERSION) {
+        // Often still compatible, so only give up if it doesn't decode.
+        log::debug!("{key:?} has version {stored_version:?}, expected {EGUI_STATE_VERSION}");
+    }
+
+    // Decoded even if the version matches: it has no patch level, and the value may be damaged.
+    // Otherwise it would be dropped without a backup when it is used.
+
+    let error = storage.codec(key).decode::<T>(&value).err()?;
+    Some(back_up(
+        storage,
+        key,
+        value,
+        stored_version,
+        EGUI_STATE_VERSION.to_owned(),
+        error.to_string(),
+    ))
+}
+
+#[cfg(feature = "persistence")]
+fn migrate_app_state(
+    storage: &mut dyn Storage,
+    app_migrations: &AppMigrations,
+) -> Option<StorageIssue> {
+    use crate::epi::APP_KEY;
+
+    let value = storage.get_string(APP_KEY)?;
+    let stored_version = storage.get_string(&version_key(APP_KEY));
+    let current_version = app_migrations.version();
+
+    let result = match stored_version.as_deref().map(str::parse::<u32>) {
+        None => Ok(0),
+        Some(Ok(version)) => Ok(version),
+        Some(Err(err)) => Err(format!("invalid version: {err}")),
+    }
+    .and_then(|version| {
+        if version == current_version {
+            Ok(None)
+        } else {
+            app_migrations.migrate(value.clone(), version).map(Some)
+        }
+    });
+
+    match result {
+        Ok(None) => None,
+        Ok(Some(migrated)) => {
+            log::info!(
+                "Migrated {APP_KEY:?} from version {} to {current_version}",
+                stored_version.as_deref().unwrap_or("0")
+            );
+            storage.set_string(APP_KEY, migrated);
+            storage.set_string(&version_key(APP_KEY), current_version.to_string());
+            None
+        }
+        Err(error) => Some(back_up(
+            storage,
+            APP_KEY,
+            value,
+            stored_version,
+            current_version.to_string(),
+            error,
+        )),
+    }
+}
+
+/// Move `value` and its version from `key` to [`backup_key`].
+#[cfg(feature = "persistence")]
+fn back_up(
+    storage: &mut dyn Storage,
+    key: &str,
+    value: String,
+    stored_version: Option<String>,
+    current_version: String,
+    error: String,
+) -> StorageIssue {
+    let backup_key = backup_key(key);
+    storage.set_string(&backup_key, value);
+    match &stored_version {
+        Some(version) => storage.set_string(&version_key(&backup_key), version.clone()),
+        None => storage.remove(&version_key(&backup_key)),
+    }
+    storage.remove(key);
+    storage.remove(&version_key(key));
+
+    StorageIssue::Undecodable {
+        key: key.to_owned(),
+        stored_version,
+        current_version,
+        backup_key,
+        error,
+    }
+}
+
+#[cfg(all(test, feature = "persistence"))]
+mod tests {
+    use crate::epi::{APP_KEY, MemoryStorage};
+
+    use super::*;
+
+    fn migrations() -> AppMigrations {
+        AppMigrations::new(2)
+            .with_migration(0, |value| Ok(value.replace("name:", "title:")))
+            .with_migration(1, |value| Ok(format!("{value}!")))
+    }
+
+    #[test]
+    fn unversioned_app_state_is_migrated_from_version_0() {
+        let mut storage = MemoryStorage::from_iter([(APP_KEY, "(name: 1)")]);
+        assert_eq!(migrate_app_state(&mut storage, &migrations()), None);
+        assert_eq!(storage.get_string(APP_KEY).as_deref(), Some("(title: 1)!"));
+        assert_eq!(
+            storage.get_string(&version_key(APP_KEY)).as_deref(),
+            Some("2")
+        );
+    }
+
+    #[test]
+    fn current_app_state_is_left_alone() {
+        let mut storage = MemoryStorage::from_iter([(APP_KEY, "(name: 1)"), ("app.version", "2")]);
+        assert_eq!(migrate_app_state(&mut storage, &migrations()), None);
+        assert_eq!(storage.get_string(APP_KEY).as_deref(), Some("(name: 1)"));
+    }
+
+    #[test]
+    fn app_state_that_cant_be_migrated_is_backed_up() {
+        for (version, expected_error) in [
+            ("3", "version 3 is newer than this app's version 2"),
+            ("two", "invalid version: invalid digit found in string"),
+        ] {
+            let mut storage =
+                MemoryStorage::from_iter([(APP_KEY, "(name: 1)"), ("app.version", version)]);
+            let issue = migrate_app_state(&mut storage, &migrations());
+            assert_eq!(
+                issue,
+                Some(StorageIssue::Undecodable {
+                    key: APP_KEY.to_owned(),
+                    stored_version: Some(version.to_owned()),
+                    current_version: "2".to_owned(),
+                    backup_key: "app.backup".to_owned(),
+                    error: expected_error.to_owned(),
+                })
+            );
+            assert_eq!(storage.keys(), ["app.backup", "app.backup.version"]);
+            assert_eq!(
+                storage.get_string("app.backup").as_deref(),
+                Some("(name: 1)")
+            );
+            assert_eq!(
+                storage.get_string("app.backup.version").as_deref(),
+                Some(version)
+            );
+        }
+    }
+
+    #[test]
+    fn back_up_drops_the_version_of_an_older_backup() {
+        let mut storage = MemoryStorage::from_iter([("key", "new"), ("key.backup.version", "1")]);
+        back_up(
+            &mut storage,
+            "key",
+            "new".to_owned(),
+            None,
+            "2".to_owned(),
+            "bad".to_owned(),
+        );
+        assert_eq!(storage.keys(), ["key.backup"]);
+    }
+
+    #[test]
+    fn undecodable_egui_state_is_backed_up_whatever_its_version() {
+        let garbage = "not RON at all (";
+
+        for version in [EGUI_STATE_VERSION, "egui-0.1"] {
+            let mut storage =
+                MemoryStorage::from_iter([("egui", garbage), ("egui.version", version)]);
+            let issues = prepare_storage(&mut storage, &AppMigrations::default());
+            assert!(
+                matches!(&issues[..], [StorageIssue::Undecodable { key, .. }] if key == "egui"),
+                "{version}: {issues:?}"
+            );
+            assert_eq!(storage.get_string("egui"), None);
+            assert_eq!(storage.get_string("egui.backup").as_deref(), Some(garbage));
+        }
+    }
+
+    #[test]
+    fn decodable_egui_state_is_kept() {
+        let memory = crate::epi::Codec::Ron
+            .encode(&egui::Memory::default())
+            .unwrap();
+        let mut storage = MemoryStorage::from_iter([
+            ("egui", memory.as_str()),
+            ("egui.version", EGUI_STATE_VERSION),
+        ]);
+        assert_eq!(prepare_storage(&mut storage, &AppMigrations::default()), []);
+        assert_eq!(storage.get_string("egui"), Some(memory));
+    }
+}
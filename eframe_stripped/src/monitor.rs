@@ -0,0 +1,532 @@
+//! Where windows go on the monitors: restoring them onto the monitor they were on
+//! (see [`MonitorPlacement`]), and centering them (see [`Centering`]).
+
+use egui::{NumExt as _, ViewportBuilder};
+use egui_winit::WindowSettings;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event_loop::ActiveEventLoop,
+    monitor::MonitorHandle,
+    window::Window,
+};
+
+/// A monitor, described well enough to recognize it in a later session.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+pub struct MonitorInfo {
+    /// As reported by the OS, e.g. `DP-1`.
+    pub name: Option<String>,
+
+    /// The top left corner on the virtual desktop, in physical pixels.
+    pub position_pixels: [i32; 2],
+
+    pub size_pixels: [u32; 2],
+
+    pub scale_factor: f64,
+}
+
+impl MonitorInfo {
+    pub fn new(monitor: &MonitorHandle) -> Self {
+        let position = monitor.position();
+        let size = monitor.size();
+        Self {
+            name: monitor.name(),
+            position_pixels: [position.x, position.y],
+            size_pixels: [size.width, size.height],
+            scale_factor: monitor.scale_factor(),
+        }
+    }
+
+    /// Could `other` be this monitor?
+    ///
+    /// Names are compared if both have one, since positions change when monitors are rearranged.
+    fn is_probably(&self, other: &Self) -> bool {
+        match (&self.name, &other.name) {
+            (Some(name), Some(other_name)) => name == other_name,
+            _ => {
+                self.position_pixels == other.position_pixels
+                    && self.size_pixels == other.size_pixels
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Which monitor a window was on, and where on it.
+///
+/// Used instead of the absolute position in [`WindowSettings`], so a window isn't
+/// clamped to some odd place when its monitor was unplugged or the monitors were rearranged.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+pub struct MonitorPlacement {
+    pub monitor: MonitorInfo,
+
+    /// The outer window position relative to the top left corner of [`Self::monitor`], in physical pixels.
+    pub offset_pixels: [i32; 2],
+
+    /// How much larger the outer size of the window was than the inner size,
+    /// i.e. the title bar and borders, in physical pixels.
+    ///
+    /// Only the inner size is known before the window is created,
+    /// so this is needed to keep all of it on the monitor.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub decoration_pixels: [u32; 2],
+}
+
+impl MonitorPlacement {
+    pub fn from_window(window: &Window) -> Option<Self> {
+        let monitor = window.current_monitor()?;
+        let position = window.outer_position().ok()?;
+        let monitor_position = monitor.position();
+        let outer_size = window.outer_size();
+        let inner_size = window.inner_size();
+        Some(Self {
+            monitor: MonitorInfo::new(&monitor),
+            offset_pixels: [
+                position.x - monitor_position.x,
+                position.y - monitor_position.y,
+            ],
+            decoration_pixels: [
+                outer_size.width.saturating_sub(inner_size.width),
+                outer_size.height.saturating_sub(inner_size.height),
+            ],
+        })
+    }
+
+    /// The same monitor if it is still connected, otherwise the primary one.
+    fn target_monitor(
+        &self,
+        monitors: impl Iterator<Item = MonitorHandle>,
+        primary: Option<MonitorHandle>,
+    ) -> Option<MonitorHandle> {
+        let monitors = monitors.map(|monitor| (MonitorInfo::new(&monitor), monitor));
+        self.pick_monitor(monitors.collect(), primary)
+    }
+
+    /// [`Self::monitor`] if it is among `monitors`, else one that [`MonitorInfo::is_probably`] it,
+    /// else `primary`, else the first one.
+    fn pick_monitor<T>(&self, monitors: Vec<(MonitorInfo, T)>, primary: Option<T>) -> Option<T> {
+        let same = monitors
+            .iter()
+            .position(|(info, _)| *info == self.monitor)
+            .or_else(|| {
+                monitors
+                    .iter()
+                    .position(|(info, _)| self.monitor.is_probably(info))
+            });
+        if same.is_none() {
+            log::debug!(
+                "Monitor {:?} is gone, restoring the window onto the primary monitor",
+                self.monitor.name
+            );
+        }
+        let mut monitors = monitors.into_iter().map(|(_, monitor)| monitor);
+        match same {
+            Some(index) => monitors.nth(index),
+            None => primary.or_else(|| monitors.next()),
+        }
+    }
+
+    /// Where to put the outer top left corner of a window of outer size `window_size`
+    /// on the monitor at `monitor_position` of `monitor_size`, keeping it on the monitor.
+    fn position_on(
+        &self,
+        monitor_position: PhysicalPosition<i32>,
+        monitor_size: PhysicalSize<u32>,
+        window_size: PhysicalSize<u32>,
+    ) -> PhysicalPosition<i32> {
+        let [x, y] = self.offset_pixels;
+        let max_x = monitor_size.width.saturating_sub(window_size.width) as i32;
+        let max_y = monitor_size.height.saturating_sub(window_size.height) as i32;
+        PhysicalPosition::new(
+            monitor_position.x + x.clamp(0, max_x),
+            monitor_position.y + y.clamp(0, max_y),
+        )
+    }
+
+    /// Move the window of `viewport_builder` onto the monitor, and shrink it to fit if it doesn't.
+    ///
+    /// Maximized and fullscreen windows end up maximized or fullscreen on that monitor.
+    pub fn restore(
+        &self,
+        egui_zoom_factor: f32,
+        event_loop: &ActiveEventLoop,
+        mut viewport_builder: ViewportBuilder,
+    ) -> ViewportBuilder {
+        let Some(monitor) = self.target_monitor(
+            event_loop.available_monitors(),
+            event_loop.primary_monitor(),
+        ) else {
+            return viewport_builder;
+        };
+
+        let pixels_per_point = egui_zoom_factor * monitor.scale_factor() as f32;
+        let monitor_size = monitor.size();
+        let monitor_size_points =
+            egui::vec2(monitor_size.width as f32, monitor_size.height as f32) / pixels_per_point;
+
+        let inner_size_points = viewport_builder.inner_size.unwrap_or_default();
+        let inner_size_points = if viewport_builder.clamp_size_to_monitor_size.unwrap_or(true) {
+            inner_size_points.at_most(monitor_size_points)
+        } else {
+            inner_size_points
+        };
+        if viewport_builder.inner_size.is_some() {
+            viewport_builder = viewport_builder.with_inner_size(inner_size_points);
+        }
+
+        let inner_size_pixels = inner_size_points * pixels_per_point;
+        let [decoration_width, decoration_height] = self.decoration_pixels;
+        let outer_size = PhysicalSize::new(
+            (inner_size_pixels.x as u32).saturating_add(decoration_width),
+            (inner_size_pixels.y as u32).saturating_add(decoration_height),
+        );
+        let mut position = self.position_on(monitor.position(), monitor_size, outer_size);
+        if cfg!(target_os = "macos") {
+            // There the builder position is the inner one, below the title bar.
+            position.y += decoration_height as i32;
+        }
+        viewport_builder
+            .with_position(egui::pos2(position.x as f32, position.y as f32) / pixels_per_point)
+    }
+
+    /// Mac sometimes doesn't put windows on secondary monitors with [`Self::restore`] alone,
+    /// like [`WindowSettings::initialize_window`] works around.
+    pub fn initialize_window(&self, window: &Window) {
+        if !cfg!(target_os = "macos") {
+            return;
+        }
+        if let Some(monitor) =
+            self.target_monitor(window.available_monitors(), window.primary_monitor())
+        {
+            window.set_outer_position(self.position_on(
+                monitor.position(),
+                monitor.size(),
+                window.outer_size(),
+            ));
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// The position and size of a window from a previous session, and the monitor it was on.
+#[derive(Clone, Debug, Default)]
+pub struct SavedWindow {
+    pub settings: WindowSettings,
+
+    /// `None` if the monitor is unknown, e.g. in settings saved by older versions.
+    pub placement: Option<MonitorPlacement>,
+}
+
+impl SavedWindow {
+    pub fn from_window(egui_zoom_factor: f32, window: &Window) -> Self {
+        Self {
+            settings: WindowSettings::from_window(egui_zoom_factor, window),
+            placement: MonitorPlacement::from_window(window),
+        }
+    }
+
+    pub fn initialize_window(&self, window: &Window) {
+        match &self.placement {
+            Some(placement) => placement.initialize_window(window),
+            None => self.settings.initialize_window(window),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Where to center a window when it is created.
+///
+/// See [`crate::epi::NativeOptions::centering`] for the root viewport,
+/// and [`crate::epi::set_viewport_centering`] for the others.
+///
+/// Wayland doesn't let apps position their windows, so there this is left to the compositor.
+/// eframe passes on the activation token it was started with, or one from the parent window,
+/// which compositors use to place and focus the new window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Centering {
+    /// Center on the primary monitor.
+    #[default]
+    PrimaryMonitor,
+
+    /// Center on the monitor the mouse cursor is on.
+    ///
+    /// The cursor position is only known after it was over one of the app's windows,
+    /// so before that (e.g. for the root viewport) this is the same as [`Self::PrimaryMonitor`].
+    CursorMonitor,
+
+    /// Center on the window of the parent viewport.
+    ///
+    /// The root viewport has no parent, so it is centered on the primary monitor.
+    Parent,
+}
+
+impl Centering {
+    /// Set the position of `viewport_builder` so its window is centered.
+    ///
+    /// `cursor_position` is in physical pixels on the virtual desktop.
+    pub(crate) fn center(
+        self,
+        egui_zoom_factor: f32,
+        event_loop: &ActiveEventLoop,
+        viewport_builder: ViewportBuilder,
+        parent: Option<&Window>,
+        cursor_position: Option<PhysicalPosition<f64>>,
+    ) -> ViewportBuilder {
+        profiling::function_scope!();
+
+        if is_wayland(event_loop) {
+            log::debug!(
+                "Wayland doesn't let apps position windows, so {self:?} is up to the compositor"
+            );
+            return viewport_builder;
+        }
+
+        let parent_area = || {
+            let parent = parent?;
+            Some(Area {
+                position: parent.outer_position().ok()?,
+                size: parent.outer_size(),
+                scale_factor: parent.scale_factor(),
+            })
+        };
+        let cursor_monitor_area = || {
+            let cursor = cursor_position?;
+            event_loop
+                .available_monitors()
+                .find(|monitor| {
+                    let (position, size) = (monitor.position(), monitor.size());
+                    (position.x as f64..position.x as f64 + size.width as f64).contains(&cursor.x)
+                        && (position.y as f64..position.y as f64 + size.height as f64)
+                            .contains(&cursor.y)
+                })
+                .map(|monitor| Area::of_monitor(&monitor))
+        };
+        let primary_monitor_area = || {
+            event_loop
+                .primary_monitor()
+                .or_else(|| event_loop.available_monitors().next())
+                .map(|monitor| Area::of_monitor(&monitor))
+        };
+
+        let area = match self {
+            Self::PrimaryMonitor => None,
+            Self::CursorMonitor => cursor_monitor_area(),
+            Self::Parent => parent_area(),
+        };
+        let Some(area) = area.or_else(primary_monitor_area) else {
+            return viewport_builder;
+        };
+        if area.size.width == 0 || area.size.height == 0 {
+            return viewport_builder;
+        }
+
+        // egui-winit converts positions with the scale factor of the monitor the window ends up on.
+        let pixels_per_point = egui_zoom_factor * area.scale_factor as f32;
+        let inner_size_points = viewport_builder
+            .inner_size
+            .unwrap_or(egui::vec2(800.0, 600.0));
+        let inner_size_pixels = inner_size_points * pixels_per_point;
+        let x = area.position.x as f32 + (area.size.width as f32 - inner_size_pixels.x) / 2.0;
+        let y = area.position.y as f32 + (area.size.height as f32 - inner_size_pixels.y) / 2.0;
+        viewport_builder.with_position(egui::pos2(x, y) / pixels_per_point)
+    }
+}
+
+/// Something to center a window on, in physical pixels.
+struct Area {
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    scale_factor: f64,
+}
+
+impl Area {
+    fn of_monitor(monitor: &MonitorHandle) -> Self {
+        Self {
+            position: monitor.position(),
+            size: monitor.size(),
+            scale_factor: monitor.scale_factor(),
+        }
+    }
+}
+
+pub(crate) use platform::{
+    is_wayland, request_activation_token, startup_activation_token, with_activation_token,
+};
+
+/// Wayland and X11.
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+))]
+mod platform {
+    use winit::{
+        event_loop::ActiveEventLoop,
+        platform::{
+            startup_notify::{
+                EventLoopExtStartupNotify as _, WindowAttributesExtStartupNotify as _,
+                WindowExtStartupNotify as _,
+            },
+            wayland::ActiveEventLoopExtWayland as _,
+        },
+        window::{ActivationToken, Window, WindowAttributes},
+    };
+
+    pub fn is_wayland(event_loop: &ActiveEventLoop) -> bool {
+        event_loop.is_wayland()
+    }
+
+    /// The token we were started with, e.g. by a launcher, for the root window.
+    pub fn startup_activation_token(event_loop: &ActiveEventLoop) -> Option<ActivationToken> {
+        let token = event_loop.read_token_from_env();
+        if token.is_some() {
+            // So child processes don't use it too.
+            winit::platform::startup_notify::reset_activation_token_env();
+        }
+        token
+    }
+
+    /// Ask for a token for the next child window of `window`. It arrives as
+    /// [`winit::event::WindowEvent::ActivationTokenDone`].
+    pub fn request_activation_token(window: &Window) {
+        if let Err(err) = window.request_activation_token() {
+            log::debug!("Failed to request an activation token: {err}");
+        }
+    }
+
+    pub fn with_activation_token(
+        window_attributes: WindowAttributes,
+        token: ActivationToken,
+    ) -> WindowAttributes {
+        window_attributes.with_activation_token(token)
+    }
+}
+
+/// No activation tokens, and windows can be positioned.
+#[cfg(not(all(
+    unix,
+    not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+)))]
+mod platform {
+    use winit::{
+        event_loop::ActiveEventLoop,
+        window::{ActivationToken, Window, WindowAttributes},
+    };
+
+    pub fn is_wayland(_event_loop: &ActiveEventLoop) -> bool {
+        false
+    }
+
+    pub fn startup_activation_token(_event_loop: &ActiveEventLoop) -> Option<ActivationToken> {
+        None
+    }
+
+    pub fn request_activation_token(_window: &Window) {}
+
+    pub fn with_activation_token(
+        window_attributes: WindowAttributes,
+        _token: ActivationToken,
+    ) -> WindowAttributes {
+        window_attributes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: Option<&str>, x: i32) -> MonitorInfo {
+        MonitorInfo {
+            name: name.map(str::to_owned),
+            position_pixels: [x, 0],
+            size_pixels: [1920, 1080],
+            scale_factor: 1.0,
+        }
+    }
+
+    fn placement(monitor: MonitorInfo, offset_pixels: [i32; 2]) -> MonitorPlacement {
+        MonitorPlacement {
+            monitor,
+            offset_pixels,
+            decoration_pixels: [0, 0],
+        }
+    }
+
+    #[test]
+    fn is_probably_compares_names_before_positions() {
+        let left = monitor(Some("DP-1"), 0);
+        assert!(left.is_probably(&monitor(Some("DP-1"), 1920)));
+        assert!(!left.is_probably(&monitor(Some("DP-2"), 0)));
+
+        // Without both names, only the geometry is left.
+        assert!(left.is_probably(&monitor(None, 0)));
+        assert!(monitor(None, 0).is_probably(&monitor(Some("DP-2"), 0)));
+        assert!(!left.is_probably(&monitor(None, 1920)));
+        let mut scaled = monitor(None, 0);
+        scaled.scale_factor = 2.0;
+        assert!(left.is_probably(&scaled));
+    }
+
+    #[test]
+    fn pick_monitor_falls_back_to_similar_then_primary_then_first() {
+        let saved = placement(monitor(Some("DP-1"), 0), [0, 0]);
+
+        let mut moved = monitor(Some("DP-1"), 1920);
+        moved.scale_factor = 2.0;
+        let monitors = || {
+            vec![
+                (monitor(Some("HDMI-1"), 0), "hdmi"),
+                (moved.clone(), "moved"),
+                (monitor(Some("DP-1"), 0), "same"),
+            ]
+        };
+        assert_eq!(
+            saved.pick_monitor(monitors(), Some("primary")),
+            Some("same")
+        );
+
+        let mut without_same = monitors();
+        without_same.pop();
+        assert_eq!(
+            saved.pick_monitor(without_same.clone(), Some("primary")),
+            Some("moved")
+        );
+
+        without_same.pop();
+        assert_eq!(
+            saved.pick_monitor(without_same.clone(), Some("primary")),
+            Some("primary")
+        );
+        assert_eq!(saved.pick_monitor(without_same, None), Some("hdmi"));
+        assert_eq!(saved.pick_monitor(Vec::<(_, &str)>::new(), None), None);
+    }
+
+    #[test]
+    fn position_on_keeps_the_outer_window_on_the_monitor() {
+        let monitor_position = PhysicalPosition::new(1920, 100);
+        let monitor_size = PhysicalSize::new(1920, 1080);
+        let window_size = PhysicalSize::new(800, 600);
+        let position_on = |offset_pixels| {
+            placement(monitor(None, 0), offset_pixels).position_on(
+                monitor_position,
+                monitor_size,
+                window_size,
+            )
+        };
+
+        assert_eq!(position_on([10, 20]), PhysicalPosition::new(1930, 120));
+        assert_eq!(position_on([-50, -50]), PhysicalPosition::new(1920, 100));
+        assert_eq!(position_on([5000, 5000]), PhysicalPosition::new(3040, 580));
+
+        // Too large windows go in the top left corner.
+        let too_large = placement(monitor(None, 0), [10, 20]).position_on(
+            monitor_position,
+            monitor_size,
+            PhysicalSize::new(4000, 4000),
+        );
+        assert_eq!(too_large, PhysicalPosition::new(1920, 100));
+    }
+}
@@ -0,0 +1,301 @@
+//! Only run one instance of an app, see [`crate::NativeOptions::single_instance`].
+//!
+//! The first instance listens on a Unix domain socket in the runtime dir.
+//! Without a runtime dir, the socket goes in a directory in the temp dir that only the user can access.
+//! Later instances connect to it, send their command-line arguments, and exit.
+//! The arguments are sent as NUL-terminated byte strings, since arguments can't contain NUL.
+
+use std::{
+    ffi::OsString,
+    io::{Read as _, Write as _},
+    os::unix::{
+        ffi::{OsStrExt as _, OsStringExt as _},
+        fs::{DirBuilderExt as _, MetadataExt as _, PermissionsExt as _},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use egui::mutex::Mutex;
+
+/// How long to wait for another instance to send its arguments.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub enum Acquired {
+    /// We are the first instance, and listen for others.
+    First(SingleInstance),
+
+    /// Another instance is running, and got our arguments.
+    AlreadyRunning,
+}
+
+/// The socket of the first instance. Removed when dropped.
+pub struct SingleInstance {
+    path: PathBuf,
+    listener: Option<UnixListener>,
+    stop: Arc<AtomicBool>,
+}
+
+/// Arguments from other instances, for the UI thread.
+#[derive(Clone, Default)]
+pub struct Received(Arc<Mutex<Vec<Vec<OsString>>>>);
+
+impl Received {
+    pub fn take(&self) -> Vec<Vec<OsString>> {
+        std::mem::take(&mut *self.0.lock())
+    }
+}
+
+impl SingleInstance {
+    /// Become the first instance of `app_id`, or forward `args` to it.
+    pub fn acquire(app_id: &str, args: &[OsString]) -> std::io::Result<Acquired> {
+        Self::acquire_at(socket_path(app_id)?, args)
+    }
+
+    fn acquire_at(path: PathBuf, args: &[OsString]) -> std::io::Result<Acquired> {
+        // Don't send our arguments to, or remove, a socket of another user.
+        match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.uid() != current_uid() => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    format!("{} belongs to another user", path.display()),
+                ));
+            }
+            Ok(_) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+
+        match UnixStream::connect(&path) {
+            Ok(mut stream) => {
+                log::info!(
+                    "Another instance is listening on {}, forwarding arguments to it",
+                    path.display()
+                );
+                for arg in args {
+                    stream.write_all(arg.as_bytes())?;
+                    stream.write_all(&[0])?;
+                }
+                return Ok(Acquired::AlreadyRunning);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
+                // Left behind by an instance that crashed.
+                log::debug!("Removing stale socket {}", path.display());
+                std::fs::remove_file(&path).ok();
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        log::debug!("Listening for other instances on {}", path.display());
+        Ok(Acquired::First(Self {
+            path,
+            listener: Some(listener),
+            stop: Default::default(),
+        }))
+    }
+
+    /// Start accepting other instances on a background thread.
+    ///
+    /// `on_receive` is called after their arguments were added to the returned [`Received`].
+    pub fn listen(&mut self, on_receive: impl Fn() + Send + 'static) -> Received {
+        let received = Received::default();
+        let Some(listener) = self.listener.take() else {
+            return received;
+        };
+
+        let stop = self.stop.clone();
+        let result = std::thread::Builder::new()
+            .name("eframe_single_instance".to_owned())
+            .spawn({
+                let received = received.clone();
+                move || {
+                    for stream in listener.incoming() {
+                        if stop.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        match stream.and_then(read_args) {
+                            Ok(args) => {
+                                log::debug!("Another instance was started with {args:?}");
+                                received.0.lock().push(args);
+                                on_receive();
+                            }
+                            Err(err) => log::warn!("Failed to read from another instance: {err}"),
+                        }
+                    }
+                }
+            });
+        if let Err(err) = result {
+            log::warn!("Failed to spawn thread to listen for other instances: {err}");
+        }
+        received
+    }
+}
+
+impl Drop for SingleInstance {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if self.listener.is_none() {
+            // Wake up the listening thread, so it sees `stop`.
+            UnixStream::connect(&self.path).ok();
+        }
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+fn read_args(mut stream: UnixStream) -> std::io::Result<Vec<OsString>> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut bytes = Vec::new();
+    stream.read_to_end(&mut bytes)?;
+    if bytes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let bytes = bytes.strip_suffix(&[0]).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "the last argument is cut off",
+        )
+    })?;
+    Ok(bytes
+        .split(|&b| b == 0)
+        .map(|arg| OsString::from_vec(arg.to_vec()))
+        .collect())
+}
+
+/// `$XDG_RUNTIME_DIR/<app_id>.eframe.sock`, or in [`user_temp_dir`] if there is no runtime dir.
+fn socket_path(app_id: &str) -> std::io::Result<PathBuf> {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+    {
+        Some(dir) => dir,
+        None => user_temp_dir(&std::env::temp_dir())?,
+    };
+    let file_name: String = app_id
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Ok(dir.join(format!("{file_name}.eframe.sock")))
+}
+
+/// `<temp_dir>/eframe-<uid>`, which only the current user may access.
+///
+/// Fails if it exists but belongs to someone else or is open to others,
+/// since they could then listen in our place.
+fn user_temp_dir(temp_dir: &Path) -> std::io::Result<PathBuf> {
+    let uid = current_uid();
+    let dir = temp_dir.join(format!("eframe-{uid}"));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(err) => return Err(err),
+    }
+
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.permissions().mode() & 0o077 != 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!(
+                "{} is not a directory private to the current user",
+                dir.display()
+            ),
+        ));
+    }
+    Ok(dir)
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: `getuid` has no preconditions and always succeeds.
+    unsafe { libc::getuid() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    fn read_bytes(bytes: &[u8]) -> std::io::Result<Vec<OsString>> {
+        let (mut sender, receiver) = UnixStream::pair().unwrap();
+        sender.write_all(bytes).unwrap();
+        drop(sender);
+        read_args(receiver)
+    }
+
+    #[test]
+    fn read_args_splits_at_nul() {
+        assert_eq!(
+            read_bytes(b"open\0\0a b\0").unwrap(),
+            args(&["open", "", "a b"])
+        );
+        assert_eq!(read_bytes(b"").unwrap(), args(&[]));
+        assert_eq!(read_bytes(b"\0").unwrap(), args(&[""]));
+    }
+
+    #[test]
+    fn read_args_rejects_cut_off_argument() {
+        let err = read_bytes(b"open\0fil").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn forwards_args_to_first_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.eframe.sock");
+
+        let Acquired::First(mut first) = SingleInstance::acquire_at(path.clone(), &[]).unwrap()
+        else {
+            panic!("nothing is listening yet");
+        };
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let received = first.listen(move || sender.send(()).unwrap());
+
+        let second = SingleInstance::acquire_at(path.clone(), &args(&["open", "file"])).unwrap();
+        assert!(matches!(second, Acquired::AlreadyRunning));
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(received.take(), [args(&["open", "file"])]);
+
+        drop(first);
+        assert!(!path.exists());
+        let third = SingleInstance::acquire_at(path, &[]).unwrap();
+        assert!(matches!(third, Acquired::First(_)));
+    }
+
+    #[test]
+    fn replaces_stale_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.eframe.sock");
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let acquired = SingleInstance::acquire_at(path, &[]).unwrap();
+        assert!(matches!(acquired, Acquired::First(_)));
+    }
+
+    #[test]
+    fn user_temp_dir_is_private() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = user_temp_dir(temp_dir.path()).unwrap();
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        assert_eq!(user_temp_dir(temp_dir.path()).unwrap(), dir);
+
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o777)).unwrap();
+        let err = user_temp_dir(temp_dir.path()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    }
+}
@@ -0,0 +1,273 @@
+//! [`Storage`] implementations that don't touch the file system.
+
+use std::collections::BTreeMap;
+
+use crate::epi::Storage;
+
+/// Keeps everything in memory, e.g. for tests.
+///
+/// Also counts how often it was flushed, so tests can check that saving happened.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    values: BTreeMap<String, String>,
+    flushes: usize,
+    #[cfg(feature = "ron")]
+    codecs: crate::epi::Codecs,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `codecs` for [`Storage::codec`].
+    #[cfg(feature = "ron")]
+    #[inline]
+    pub fn with_codecs(mut self, codecs: crate::epi::Codecs) -> Self {
+        self.codecs = codecs;
+        self
+    }
+
+    /// Everything stored, sorted by key.
+    pub fn values(&self) -> &BTreeMap<String, String> {
+        &self.values
+    }
+
+    /// How often [`Storage::flush`] was called.
+    pub fn flushes(&self) -> usize {
+        self.flushes
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for MemoryStorage {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self {
+            values: iter
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        self.values.insert(key.to_owned(), value);
+    }
+
+    fn flush(&mut self) {
+        self.flushes += 1;
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.values.remove(key);
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.values.keys().cloned().collect()
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    #[cfg(feature = "ron")]
+    fn codec(&self, key: &str) -> crate::epi::Codec {
+        self.codecs.get(key)
+    }
+}
+
+/// Reads from a user layer, falling back to a read-only base layer, and writes only to the user layer.
+///
+/// For example, the base can hold system-wide defaults set by an administrator,
+/// while the user layer holds the user's own settings.
+/// [`Storage::remove`] resets a setting back to the base value,
+/// if the user layer implements [`Storage::remove`].
+pub struct OverlayStorage {
+    base: Box<dyn Storage>,
+    user: Box<dyn Storage>,
+}
+
+impl OverlayStorage {
+    pub fn new(base: Box<dyn Storage>, user: Box<dyn Storage>) -> Self {
+        Self { base, user }
+    }
+
+    /// The read-only layer with the defaults.
+    pub fn base(&self) -> &dyn Storage {
+        self.base.as_ref()
+    }
+
+    /// The layer all changes are written to.
+    pub fn user(&self) -> &dyn Storage {
+        self.user.as_ref()
+    }
+
+    pub fn user_mut(&mut self) -> &mut dyn Storage {
+        self.user.as_mut()
+    }
+
+    /// Is the value for `key` set in the user layer, rather than coming from the base?
+    pub fn is_overridden(&self, key: &str) -> bool {
+        self.user.get_string(key).is_some()
+    }
+
+    pub fn into_layers(self) -> (Box<dyn Storage>, Box<dyn Storage>) {
+        (self.base, self.user)
+    }
+}
+
+impl Storage for OverlayStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.user
+            .get_string(key)
+            .or_else(|| self.base.get_string(key))
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        self.user.set_string(key, value);
+    }
+
+    /// Only flushes the user layer, the base is never written to.
+    fn flush(&mut self) {
+        self.user.flush();
+    }
+
+    /// Removes the user's value, so the base value (if any) shows through again.
+    fn remove(&mut self, key: &str) {
+        self.user.remove(key);
+    }
+
+    /// Keys from both layers, sorted and without duplicates.
+    fn keys(&self) -> Vec<String> {
+        let mut keys = self.base.keys();
+        keys.extend(self.user.keys());
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+
+    /// Clears the user layer, resetting everything to the base values.
+    fn clear(&mut self) {
+        self.user.clear();
+    }
+
+    /// Watches both layers.
+    fn watch(&mut self, on_change: Box<dyn Fn() + Send + Sync>) -> bool {
+        let on_change: std::sync::Arc<dyn Fn() + Send + Sync> = on_change.into();
+        let base = self.base.watch(Box::new({
+            let on_change = on_change.clone();
+            move || on_change()
+        }));
+        let user = self.user.watch(Box::new(move || on_change()));
+        base || user
+    }
+
+    /// Changes in either layer, sorted and without duplicates.
+    fn reload(&mut self) -> Vec<String> {
+        let mut keys = self.base.reload();
+        keys.extend(self.user.reload());
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+
+    /// The codec of the user layer, since that's where values are written.
+    /// The base should use the same codecs.
+    #[cfg(feature = "ron")]
+    fn codec(&self, key: &str) -> crate::epi::Codec {
+        self.user.codec(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlay() -> OverlayStorage {
+        OverlayStorage::new(
+            Box::new(MemoryStorage::from_iter([
+                ("theme", "dark"),
+                ("font", "14"),
+            ])),
+            Box::new(MemoryStorage::from_iter([
+                ("theme", "light"),
+                ("mine", "1"),
+            ])),
+        )
+    }
+
+    #[test]
+    fn overlay_reads_user_then_base() {
+        let storage = overlay();
+        assert_eq!(storage.get_string("theme").as_deref(), Some("light"));
+        assert_eq!(storage.get_string("font").as_deref(), Some("14"));
+        assert_eq!(storage.get_string("mine").as_deref(), Some("1"));
+        assert_eq!(storage.get_string("missing"), None);
+        assert!(storage.is_overridden("theme"));
+        assert!(!storage.is_overridden("font"));
+    }
+
+    #[test]
+    fn overlay_writes_only_to_the_user_layer() {
+        let mut storage = overlay();
+        storage.set_string("font", "20".to_owned());
+        storage.flush();
+        assert_eq!(storage.get_string("font").as_deref(), Some("20"));
+        assert_eq!(storage.base().get_string("font").as_deref(), Some("14"));
+        assert_eq!(storage.user().get_string("font").as_deref(), Some("20"));
+    }
+
+    #[test]
+    fn overlay_remove_restores_the_base_value() {
+        let mut storage = overlay();
+        storage.remove("theme");
+        assert_eq!(storage.get_string("theme").as_deref(), Some("dark"));
+        storage.remove("font");
+        assert_eq!(storage.get_string("font").as_deref(), Some("14"));
+    }
+
+    #[test]
+    fn overlay_keys_are_sorted_and_deduplicated() {
+        assert_eq!(overlay().keys(), ["font", "mine", "theme"]);
+    }
+
+    #[test]
+    fn overlay_clear_resets_to_the_base() {
+        let mut storage = overlay();
+        storage.clear();
+        assert_eq!(storage.get_string("theme").as_deref(), Some("dark"));
+        assert_eq!(storage.get_string("mine"), None);
+        assert_eq!(storage.keys(), ["font", "theme"]);
+        assert!(storage.user().keys().is_empty());
+    }
+
+    /// A storage written before [`Storage::remove`] existed.
+    struct OldStorage(MemoryStorage);
+
+    impl Storage for OldStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get_string(key)
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.set_string(key, value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn default_remove_keeps_the_value() {
+        let mut storage = OldStorage(MemoryStorage::from_iter([("key", "value")]));
+        storage.remove("key");
+        storage.clear();
+        assert_eq!(storage.get_string("key").as_deref(), Some("value"));
+        assert!(storage.keys().is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+//! Run an [`App`] without a window, e.g. in unit tests.
+//!
+//! ```
+//! use eframe_stripped::testing::TestHarness;
+//!
+//! struct Quitter;
+//!
+//! impl eframe_stripped::epi::App for Quitter {
+//!     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe_stripped::epi::Frame) {
+//!         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+//!     }
+//! }
+//!
+//! let mut harness = TestHarness::new(Box::new(|_| Ok(Box::new(Quitter)))).unwrap();
+//! let output = harness.step();
+//! assert!(TestHarness::close_requested(&output));
+//! ```
+
+use egui::{FullOutput, ViewportCommand, ViewportId};
+
+use crate::epi::{App, AppCreator, CreationContext, Frame, MemoryStorage, Storage};
+
+type DynError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Seconds between two [`TestHarness::step`]s, as seen by the app through [`egui::InputState::time`].
+pub const STEP_DT: f64 = 1.0 / 60.0;
+
+/// Drives an [`App`] frame by frame, without a window, GL context or event loop.
+pub struct TestHarness<'app> {
+    egui_ctx: egui::Context,
+    app: Box<dyn 'app + App>,
+    frame: Frame,
+    frame_nr: u64,
+
+    /// Used for every frame that isn't given explicit input.
+    pub screen_rect: egui::Rect,
+}
+
+impl<'app> TestHarness<'app> {
+    /// Create the app with a [`MemoryStorage`] that starts out empty.
+    pub fn new(app_creator: AppCreator<'app>) -> Result<Self, DynError> {
+        Self::with_storage(Box::new(MemoryStorage::new()), app_creator)
+    }
+
+    /// Create the app with `storage` available in [`CreationContext::storage`] and [`App::save`].
+    pub fn with_storage(
+        storage: Box<dyn Storage>,
+        app_creator: AppCreator<'app>,
+    ) -> Result<Self, DynError> {
+        let egui_ctx = egui::Context::default();
+
+        let mut frame = Frame::_new_kittest();
+        frame.storage = Some(storage);
+
+        let mut cc = CreationContext::_new_kittest(egui_ctx.clone());
+        cc.storage = frame.storage.as_deref();
+        let app = app_creator(&cc)?;
+
+        Ok(Self {
+            egui_ctx,
+            app,
+            frame,
+            frame_nr: 0,
+            screen_rect: egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0)),
+        })
+    }
+
+    /// Run one frame without any input events.
+    pub fn step(&mut self) -> FullOutput {
+        self.step_with_events(Vec::new())
+    }
+
+    /// Run one frame with the given input events.
+    pub fn step_with_events(&mut self, events: Vec<egui::Event>) -> FullOutput {
+        self.step_with_input(egui::RawInput {
+            screen_rect: Some(self.screen_rect),
+            events,
+            ..Default::default()
+        })
+    }
+
+    /// Run one frame with `raw_input`, like the native integration does.
+    ///
+    /// If [`egui::RawInput::time`] is `None`, it is set to [`STEP_DT`] times the number of previous frames.
+    pub fn step_with_input(&mut self, mut raw_input: egui::RawInput) -> FullOutput {
+        raw_input.time.get_or_insert(self.frame_nr as f64 * STEP_DT);
+        self.frame_nr += 1;
+
+        self.app.raw_input_hook(&self.egui_ctx, &mut raw_input);
+        let Self {
+            egui_ctx,
+            app,
+            frame,
+            ..
+        } = self;
+        egui_ctx.run(raw_input, |egui_ctx| app.update(egui_ctx, frame))
+    }
+
+    /// Step until `predicate` returns `true` for a frame's output, at most `max_steps` times.
+    ///
+    /// Returns the output of the frame that satisfied `predicate`, or `None`.
+    pub fn step_until(
+        &mut self,
+        max_steps: usize,
+        mut predicate: impl FnMut(&FullOutput) -> bool,
+    ) -> Option<FullOutput> {
+        (0..max_steps)
+            .map(|_| self.step())
+            .find(|output| predicate(output))
+    }
+
+    /// Call [`App::save`] and flush the storage, like the integration does on exit and auto-save.
+    pub fn save(&mut self) {
+        if let Some(storage) = self.frame.storage.as_deref_mut() {
+            self.app.save(storage);
+            storage.flush();
+        }
+    }
+
+    /// The storage given to the app.
+    pub fn storage(&self) -> Option<&dyn Storage> {
+        self.frame.storage.as_deref()
+    }
+
+    /// The number of frames run so far.
+    pub fn frame_nr(&self) -> u64 {
+        self.frame_nr
+    }
+
+    pub fn egui_ctx(&self) -> &egui::Context {
+        &self.egui_ctx
+    }
+
+    pub fn app(&self) -> &dyn App {
+        self.app.as_ref()
+    }
+
+    pub fn app_mut(&mut self) -> &mut dyn App {
+        self.app.as_mut()
+    }
+
+    pub fn frame_mut(&mut self) -> &mut Frame {
+        &mut self.frame
+    }
+
+    /// The commands sent to the viewport `viewport_id` during the frame that produced `output`.
+    pub fn viewport_commands(output: &FullOutput, viewport_id: ViewportId) -> &[ViewportCommand] {
+        output
+            .viewport_output
+            .get(&viewport_id)
+            .map_or(&[], |viewport_output| &viewport_output.commands)
+    }
+
+    /// Did the app send [`ViewportCommand::Close`] to the root viewport?
+    pub fn close_requested(output: &FullOutput) -> bool {
+        Self::viewport_commands(output, ViewportId::ROOT).contains(&ViewportCommand::Close)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts frames, and saves the count.
+    #[derive(Default)]
+    struct Counter {
+        frames: u32,
+    }
+
+    impl App for Counter {
+        fn update(&mut self, _ctx: &egui::Context, _frame: &mut Frame) {
+            self.frames += 1;
+        }
+
+        fn save(&mut self, storage: &mut dyn Storage) {
+            storage.set_string("frames", self.frames.to_string());
+        }
+    }
+
+    #[test]
+    fn save_writes_storage() {
+        let mut harness = TestHarness::new(Box::new(|_| Ok(Box::new(Counter::default())))).unwrap();
+        harness.step();
+        harness.step();
+        harness.save();
+
+        let storage = harness.storage().unwrap();
+        assert_eq!(storage.get_string("frames").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn app_creator_sees_storage() {
+        let storage = MemoryStorage::from_iter([("frames", "41")]);
+
+        let harness = TestHarness::with_storage(
+            Box::new(storage),
+            Box::new(|cc| {
+                let frames = cc
+                    .storage
+                    .and_then(|storage| storage.get_string("frames"))
+                    .and_then(|frames| frames.parse().ok())
+                    .unwrap_or_default();
+                Ok(Box::new(Counter { frames }))
+            }),
+        );
+        let mut harness = harness.unwrap();
+        harness.step();
+        harness.save();
+
+        let storage = harness.storage().unwrap();
+        assert_eq!(storage.get_string("frames").as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn time_advances_per_step() {
+        let mut harness = TestHarness::new(Box::new(|_| Ok(Box::new(Counter::default())))).unwrap();
+        harness.step();
+        harness.step();
+        let time = harness.egui_ctx().input(|i| i.time);
+        assert!((time - STEP_DT).abs() < 1e-9, "time was {time}");
+    }
+}
@@ -0,0 +1,216 @@
+//! A CPU-side copy of every texture uploaded to the painter.
+//!
+//! egui only hands us texture deltas once, so if the GL context is lost
+//! this is the only way to get the textures back onto the GPU.
+//! The copy costs memory, so it is only kept when the context can report being lost.
+
+use std::sync::Arc;
+
+use ahash::HashMap;
+use egui::{
+    ColorImage, TextureId, TexturesDelta,
+    epaint::{FontImage, ImageData, ImageDelta},
+};
+
+#[derive(Default)]
+pub struct TextureMirror {
+    /// The size of every texture, also when we don't keep a copy.
+    sizes: HashMap<TextureId, [usize; 2]>,
+
+    /// `None` if we only track the sizes.
+    textures: Option<HashMap<TextureId, ImageDelta>>,
+}
+
+impl TextureMirror {
+    /// If `keep_copy` is `false`, only the sizes are tracked, and [`Self::full_deltas`] returns `None`.
+    pub fn new(keep_copy: bool) -> Self {
+        Self {
+            sizes: Default::default(),
+            textures: keep_copy.then(Default::default),
+        }
+    }
+
+    /// Apply the same changes that are about to be sent to the painter.
+    pub fn apply(&mut self, textures_delta: &TexturesDelta) {
+        profiling::function_scope!();
+
+        for (id, delta) in &textures_delta.set {
+            if delta.pos.is_none() {
+                self.sizes.insert(*id, delta.image.size());
+            }
+            let Some(textures) = &mut self.textures else {
+                continue;
+            };
+            match (delta.pos, textures.get_mut(id)) {
+                (Some(pos), Some(whole)) => {
+                    whole.options = delta.options;
+                    patch(&mut whole.image, pos, &delta.image);
+                }
+                (Some(_), None) => {
+                    log::warn!("Partial update of unknown texture {id:?}");
+                }
+                (None, _) => {
+                    textures.insert(*id, delta.clone());
+                }
+            }
+        }
+
+        for id in &textures_delta.free {
+            self.sizes.remove(id);
+            if let Some(textures) = &mut self.textures {
+                textures.remove(id);
+            }
+        }
+    }
+
+    /// Roughly how much GPU memory the textures use, assuming four bytes per texel.
+    pub fn byte_size(&self) -> usize {
+        self.sizes.values().map(|[w, h]| 4 * w * h).sum()
+    }
+
+    /// Deltas that recreate every known texture from scratch, if we kept a copy.
+    pub fn full_deltas(&self) -> Option<impl Iterator<Item = (&TextureId, &ImageDelta)>> {
+        Some(self.textures.as_ref()?.iter())
+    }
+}
+
+/// Copy `region` into `whole` with its top-left corner at `pos`.
+fn patch(whole: &mut ImageData, pos: [usize; 2], region: &ImageData) {
+    match (whole, region) {
+        (ImageData::Color(whole), ImageData::Color(region)) => {
+            let whole: &mut ColorImage = Arc::make_mut(whole);
+            copy_rows(
+                &mut whole.pixels,
+                whole.size,
+                pos,
+                &region.pixels,
+                region.size,
+            );
+        }
+        (ImageData::Font(whole), ImageData::Font(region)) => {
+            let FontImage { size, pixels } = whole;
+            copy_rows(pixels, *size, pos, &region.pixels, region.size);
+        }
+        _ => log::warn!("Mismatching image types in texture patch"),
+    }
+}
+
+fn copy_rows<T: Copy>(
+    dst: &mut [T],
+    [dst_width, dst_height]: [usize; 2],
+    [x, y]: [usize; 2],
+    src: &[T],
+    [src_width, src_height]: [usize; 2],
+) {
+    let width = src_width.min(dst_width.saturating_sub(x));
+    let height = src_height.min(dst_height.saturating_sub(y));
+    for row in 0..height {
+        let dst_start = (y + row) * dst_width + x;
+        let src_start = row * src_width;
+        dst[dst_start..dst_start + width].copy_from_slice(&src[src_start..src_start + width]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{Color32, TextureOptions};
+
+    use super::*;
+
+    fn color_image(size: [usize; 2], color: Color32) -> ImageData {
+        ColorImage::new(size, color).into()
+    }
+
+    fn pixels(mirror: &TextureMirror, id: TextureId) -> Vec<Color32> {
+        let (_, delta) = mirror
+            .full_deltas()
+            .unwrap()
+            .find(|(i, _)| **i == id)
+            .unwrap();
+        match &delta.image {
+            ImageData::Color(image) => image.pixels.clone(),
+            ImageData::Font(_) => panic!("expected a color image"),
+        }
+    }
+
+    #[test]
+    fn copy_rows_places_region_and_clips_at_the_edges() {
+        let mut dst = vec![0; 4 * 3];
+        copy_rows(&mut dst, [4, 3], [1, 1], &[1, 2, 3, 4], [2, 2]);
+        assert_eq!(dst, [0, 0, 0, 0, 0, 1, 2, 0, 0, 3, 4, 0]);
+
+        let mut dst = vec![0; 3 * 2];
+        copy_rows(&mut dst, [3, 2], [2, 1], &[1, 2, 3, 4], [2, 2]);
+        assert_eq!(dst, [0, 0, 0, 0, 0, 1]);
+
+        let mut dst = vec![0; 2 * 2];
+        copy_rows(&mut dst, [2, 2], [5, 5], &[1], [1, 1]);
+        assert_eq!(dst, [0; 4]);
+    }
+
+    #[test]
+    fn patch_updates_part_of_a_color_image() {
+        let mut whole = color_image([2, 2], Color32::BLACK);
+        patch(&mut whole, [1, 0], &color_image([1, 2], Color32::WHITE));
+        let ImageData::Color(whole) = whole else {
+            unreachable!()
+        };
+        assert_eq!(
+            whole.pixels,
+            [
+                Color32::BLACK,
+                Color32::WHITE,
+                Color32::BLACK,
+                Color32::WHITE
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_follows_set_patch_and_free() {
+        let id = TextureId::Managed(1);
+        let mut mirror = TextureMirror::new(true);
+
+        let mut delta = TexturesDelta::default();
+        delta.set.push((
+            id,
+            ImageDelta::full(color_image([2, 1], Color32::BLACK), TextureOptions::LINEAR),
+        ));
+        mirror.apply(&delta);
+        assert_eq!(mirror.byte_size(), 4 * 2);
+
+        let mut delta = TexturesDelta::default();
+        delta.set.push((
+            id,
+            ImageDelta::partial(
+                [1, 0],
+                color_image([1, 1], Color32::WHITE),
+                TextureOptions::NEAREST,
+            ),
+        ));
+        mirror.apply(&delta);
+        assert_eq!(pixels(&mirror, id), [Color32::BLACK, Color32::WHITE]);
+        let (_, whole) = mirror.full_deltas().unwrap().next().unwrap();
+        assert_eq!(whole.options, TextureOptions::NEAREST);
+        assert_eq!(whole.pos, None);
+
+        let mut delta = TexturesDelta::default();
+        delta.free.push(id);
+        mirror.apply(&delta);
+        assert_eq!(mirror.full_deltas().unwrap().count(), 0);
+        assert_eq!(mirror.byte_size(), 0);
+    }
+
+    #[test]
+    fn without_a_copy_only_sizes_are_tracked() {
+        let mut mirror = TextureMirror::new(false);
+        let mut delta = TexturesDelta::default();
+        delta.set.push((
+            TextureId::Managed(0),
+            ImageDelta::full(color_image([4, 4], Color32::BLACK), TextureOptions::LINEAR),
+        ));
+        mirror.apply(&delta);
+        assert_eq!(mirror.byte_size(), 4 * 16);
+        assert!(mirror.full_deltas().is_none());
+    }
+}
@@ -5,14 +5,13 @@ use egui::{
     DeferredViewportUiCallback, ImmediateViewport, ViewportBuilder, ViewportClass, ViewportId,
     ViewportIdMap, ViewportIdPair, ViewportInfo, ViewportOutput,
 };
-use egui_winit::ActionRequested;
 use glutin::{
     config::GlConfig as _,
     display::GetGlDisplay as _,
     prelude::{GlDisplay as _, NotCurrentGlContext as _, PossiblyCurrentGlContext as _},
     surface::GlSurface as _,
 };
-use raw_window_handle::HasWindowHandle as _;
+use raw_window_handle::{HasDisplayHandle as _, HasWindowHandle as _};
 use winit::{
     event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy},
     window::{Window, WindowId},
@@ -20,12 +19,16 @@ use winit::{
 
 use crate::{
     NativeOptions, UserEvent,
-    epi::{App, AppCreator, CreationContext, HardwareAcceleration, Storage},
+    epi::{self, App, AppCreator, CreationContext, HardwareAcceleration, Storage},
     epi_native::{
-        EpiIntegration, apply_window_settings, create_storage, create_storage_with_file,
-        load_window_settings, viewport_builder,
+        EpiIntegration, PersistedViewport, apply_window_settings, create_storage,
+        create_storage_with_file, load_viewport_window_settings, load_window_settings,
+        viewport_builder,
     },
-    event_loop_context,
+    event_loop_context, immediate_viewport,
+    monitor::{self, SavedWindow},
+    stopwatch::Stopwatch,
+    texture_mirror::TextureMirror,
     winit_integration::{EventResult, WinitApp, create_egui_context},
 };
 
@@ -40,6 +43,9 @@ pub struct GlowWinitApp<'app> {
     // re-initializing the `GlowWinitRunning` state on Android if the application
     // suspends and resumes.
     app_creator: Option<AppCreator<'app>>,
+
+    #[cfg(unix)]
+    single_instance: Option<crate::single_instance::SingleInstance>,
 }
 
 /// State that is initialized when the application is first starts running via
@@ -55,6 +61,9 @@ struct GlowWinitRunning<'app> {
 
     // NOTE: one painter shared by all viewports.
     painter: Rc<RefCell<egui_glow::Painter>>,
+
+    /// Renders the immediate viewports of this app (and no other) while alive.
+    _immediate_renderer: immediate_viewport::RendererGuard,
 }
 
 /// This struct will contain both persistent and temporary glutin state.
@@ -78,6 +87,14 @@ struct GlutinWindowContext {
 
     max_texture_side: Option<usize>,
 
+    /// What was uploaded to the painter, so it can be re-uploaded if the context is lost.
+    ///
+    /// Only keeps a copy of the textures when [`NativeOptions::gl_robustness`] reports resets.
+    textures: TextureMirror,
+
+    /// Transparency was requested, and the GL config supports it.
+    transparent: bool,
+
     current_gl_context: Option<glutin::context::PossiblyCurrentContext>,
     not_current_gl_context: Option<glutin::context::NotCurrentContext>,
 
@@ -87,12 +104,26 @@ struct GlutinWindowContext {
     window_from_viewport: ViewportIdMap<WindowId>,
 
     focused_viewport: Option<ViewportId>,
+
+    /// The last known window settings of viewports that aren't open,
+    /// used when they are opened, and saved with the open ones.
+    window_settings: ViewportIdMap<SavedWindow>,
+
+    /// The last known position of the mouse cursor on the virtual desktop, in physical pixels.
+    cursor_position: Option<winit::dpi::PhysicalPosition<f64>>,
+
+    /// For creating child windows of these viewports, so the compositor can place and focus them.
+    activation_tokens: ViewportIdMap<winit::window::ActivationToken>,
 }
 
 struct Viewport {
     ids: ViewportIdPair,
     class: ViewportClass,
     builder: ViewportBuilder,
+
+    /// Where the window goes when it is created, if not where [`Self::builder`] says.
+    initial_placement: Option<InitialPlacement>,
+
     deferred_commands: Vec<egui::viewport::ViewportCommand>,
     info: ViewportInfo,
     actions_requested: HashSet<egui_winit::ActionRequested>,
@@ -108,6 +139,36 @@ struct Viewport {
     egui_winit: Option<egui_winit::State>,
 }
 
+/// The window geometry from restoring or centering a viewport.
+///
+/// Kept out of [`Viewport::builder`], so [`ViewportBuilder::patch`] doesn't undo it
+/// when the app shows the viewport again with its own position or size.
+struct InitialPlacement {
+    position: Option<egui::Pos2>,
+    inner_size: Option<egui::Vec2>,
+    maximized: Option<bool>,
+    fullscreen: Option<bool>,
+}
+
+impl InitialPlacement {
+    fn new(placed: &ViewportBuilder) -> Self {
+        Self {
+            position: placed.position,
+            inner_size: placed.inner_size,
+            maximized: placed.maximized,
+            fullscreen: placed.fullscreen,
+        }
+    }
+
+    fn apply(&self, mut builder: ViewportBuilder) -> ViewportBuilder {
+        builder.position = self.position.or(builder.position);
+        builder.inner_size = self.inner_size.or(builder.inner_size);
+        builder.maximized = self.maximized.or(builder.maximized);
+        builder.fullscreen = self.fullscreen.or(builder.fullscreen);
+        builder
+    }
+}
+
 // impl<'app> GlowWinitApp<'app> {
 //     pub fn new(
 //         event_loop: &EventLoop<UserEvent>,
@@ -151,6 +212,27 @@ impl WinitApp for GlowWinitApp<'_> {
             .and_then(|r| r.glutin.borrow().window_from_viewport.get(&id).copied())
     }
 
+    fn request_redraw(&self, window_id: WindowId) -> bool {
+        if let Some(window) = self.window(window_id) {
+            window.request_redraw();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn max_fps(&self) -> Option<f32> {
+        self.running
+            .as_ref()
+            .and_then(|r| r.integration.frame.max_fps())
+    }
+
+    fn debug_overlay(&mut self) -> Option<&mut crate::debug_overlay::DebugOverlay> {
+        self.running
+            .as_mut()
+            .map(|r| &mut r.integration.debug_overlay)
+    }
+
     fn save(&mut self) {
         log::debug!("WinitApp::save called");
         // if let Some(running) = self.running.as_mut() {
@@ -169,25 +251,45 @@ impl WinitApp for GlowWinitApp<'_> {
         if let Some(mut running) = self.running.take() {
             profiling::function_scope!();
 
+            let glutin = running.glutin.borrow();
             running.integration.save(
                 running.app.as_mut(),
-                Some(&running.glutin.borrow().window(ViewportId::ROOT)),
+                Some(&glutin.window(ViewportId::ROOT)),
+                &glutin.persisted_viewports(),
             );
+            drop(glutin);
             running.app.on_exit(Some(running.painter.borrow().gl()));
             running.painter.borrow_mut().destroy();
         }
     }
 
+    fn destroy(&mut self) {
+        if let Some(running) = self.running.take() {
+            profiling::function_scope!();
+            running.painter.borrow_mut().destroy();
+        }
+    }
+
     // Returning `EventResult::Wait` causes a window to never open, but for a panel icon to appear.
     fn run_ui_and_paint(
         &mut self,
         event_loop: &ActiveEventLoop,
         window_id: WindowId,
     ) -> Result<EventResult, crate::Error> {
-        if let Some(running) = &mut self.running {
-            running.run_ui_and_paint(event_loop, window_id)
-        } else {
-            Ok(EventResult::Wait)
+        let Some(running) = &mut self.running else {
+            return Ok(EventResult::Wait);
+        };
+
+        match running.run_ui_and_paint(event_loop, window_id) {
+            // Other errors, like a bad surface, don't mean the textures are gone.
+            Err(crate::Error::Glutin(err))
+                if err.error_kind() == glutin::error::ErrorKind::ContextLost
+                    && running.glutin.borrow().textures.full_deltas().is_some() =>
+            {
+                log::warn!("Lost the OpenGL context ({err}), recreating it…");
+                self.recreate_gl_context(event_loop)
+            }
+            result => result,
         }
     }
 
@@ -250,6 +352,13 @@ impl WinitApp for GlowWinitApp<'_> {
         window_id: WindowId,
         event: winit::event::WindowEvent,
     ) -> Result<EventResult, crate::Error> {
+        if let Some(running) = &self.running {
+            running
+                .glutin
+                .borrow_mut()
+                .on_window_event(window_id, &event);
+        }
+
         // if let Some(running) = &mut self.running {
         //     Ok(running.on_window_event(window_id, &event))
         // } else {
@@ -305,6 +414,9 @@ impl GlowWinitRunning<'_> {
             let Some(window) = viewport.window.as_ref() else {
                 return Ok(EventResult::Wait);
             };
+            if viewport_id == ViewportId::ROOT {
+                self.integration.pre_update(window, &viewport.builder);
+            }
 
             let Some(egui_winit) = viewport.egui_winit.as_mut() else {
                 return Ok(EventResult::Wait);
@@ -318,6 +430,9 @@ impl GlowWinitRunning<'_> {
                 .map(|(id, viewport)| (*id, viewport.info.clone()))
                 .collect();
 
+            self.integration.debug_overlay.viewport_count = glutin.viewports.len();
+            self.integration.debug_overlay.texture_bytes = glutin.textures.byte_size();
+
             (raw_input, viewport_ui_cb)
         };
 
@@ -325,9 +440,12 @@ impl GlowWinitRunning<'_> {
         // The update function, which could call immediate viewports,
         // so make sure we don't hold any locks here required by the immediate viewports rendeer.
 
+        let mut update_time = Stopwatch::new();
+        update_time.start();
         let full_output =
             self.integration
                 .update(self.app.as_mut(), viewport_ui_cb.as_deref(), raw_input);
+        update_time.pause();
 
         // ------------------------------------------------------------
 
@@ -350,11 +468,19 @@ impl GlowWinitRunning<'_> {
         } = full_output;
 
         glutin.remove_viewports_not_in(&viewport_output);
+        glutin.textures.apply(&textures_delta);
+        glutin.set_vsync(integration.frame.vsync());
+
+        let mut tessellate_time = Stopwatch::new();
+        tessellate_time.start();
+        let clipped_primitives = integration.egui_ctx.tessellate(shapes, pixels_per_point);
+        tessellate_time.pause();
 
         let GlutinWindowContext {
             viewports,
             current_gl_context,
             not_current_gl_context,
+            transparent,
             ..
         } = &mut *glutin;
 
@@ -363,21 +489,64 @@ impl GlowWinitRunning<'_> {
         };
 
         viewport.info.events.clear(); // they should have been processed
-        let gl_surface = viewport.gl_surface.as_ref().unwrap();
+
+        let (Some(egui_winit), Some(window), Some(gl_surface)) = (
+            &mut viewport.egui_winit,
+            &viewport.window,
+            &viewport.gl_surface,
+        ) else {
+            return Ok(EventResult::Wait);
+        };
+
+        let screen_size_in_pixels: [u32; 2] = window.inner_size().into();
+
+        let mut paint_time = Stopwatch::new();
+        paint_time.start();
+
+        change_gl_context(current_gl_context, not_current_gl_context, gl_surface);
+
+        {
+            let mut painter = painter.borrow_mut();
+            let clear_color = app.clear_color(&integration.egui_ctx.style().visuals);
+            painter.clear(
+                screen_size_in_pixels,
+                framebuffer_clear_color(clear_color, *transparent),
+            );
+            painter.paint_and_update_textures(
+                screen_size_in_pixels,
+                pixels_per_point,
+                &clipped_primitives,
+                &textures_delta,
+            );
+        }
+        paint_time.pause();
 
         // Required to draw the window
+        let mut swap_time = Stopwatch::new();
         {
             // vsync - don't count as frame-time:
             profiling::scope!("swap_buffers");
-            let context = current_gl_context
-                .as_ref()
-                .ok_or(egui_glow::PainterError::from(
-                    "failed to get current context to swap buffers".to_owned(),
-                ))?;
+            let context = current_gl_context.as_ref().ok_or_else(|| {
+                log::error!("failed to get current context to swap buffers");
+                glutin::error::Error::from(glutin::error::ErrorKind::BadContext)
+            })?;
 
+            swap_time.start();
             gl_surface.swap_buffers(context)?;
+            swap_time.pause();
         }
 
+        if viewport_id == ViewportId::ROOT {
+            integration.report_frame_time(crate::epi::FrameTimings {
+                update: update_time.total_time_sec(),
+                tessellate: tessellate_time.total_time_sec(),
+                paint: paint_time.total_time_sec(),
+                swap: swap_time.total_time_sec(),
+            });
+        }
+
+        egui_winit.handle_platform_output(window, platform_output);
+
         // Required
         glutin.handle_viewport_output(event_loop, &integration.egui_ctx, &viewport_output);
 
@@ -409,43 +578,26 @@ impl GlutinWindowContext {
             HardwareAcceleration::Preferred => None,
             HardwareAcceleration::Off => Some(false),
         };
-        let swap_interval = if native_options.vsync {
-            glutin::surface::SwapInterval::Wait(NonZeroU32::MIN)
-        } else {
-            glutin::surface::SwapInterval::DontWait
-        };
+        let swap_interval = swap_interval(native_options.vsync);
         /*  opengl setup flow goes like this:
             1. we create a configuration for opengl "Display" / "Config" creation
             2. choose between special extensions like glx or egl or wgl and use them to create config/display
             3. opengl context configuration
             4. opengl context creation
         */
-        // start building config for gl display
+        // start building config for gl display.
+        // Multisampling and transparency are not part of the template:
+        // instead of failing when they can't be met, we rank the configs by them (see `rank_configs`).
         let config_template_builder = glutin::config::ConfigTemplateBuilder::new()
             .prefer_hardware_accelerated(hardware_acceleration)
             .with_depth_size(native_options.depth_buffer)
-            .with_stencil_size(native_options.stencil_buffer)
-            .with_transparency(native_options.viewport.transparent.unwrap_or(false));
-        // we don't know if multi sampling option is set. so, check if its more than 0.
-        let config_template_builder = if native_options.multisampling > 0 {
-            config_template_builder.with_multisampling(
-                native_options
-                    .multisampling
-                    .try_into()
-                    .expect("failed to fit multisamples option of native_options into u8"),
-            )
-        } else {
-            config_template_builder
-        };
+            .with_stencil_size(native_options.stencil_buffer);
 
         log::debug!("trying to create glutin Display with config: {config_template_builder:?}");
 
         // Create GL display. This may probably create a window too on most platforms. Definitely on `MS windows`. Never on Android.
         let display_builder = glutin_winit::DisplayBuilder::new()
-            // we might want to expose this option to users in the future. maybe using an env var or using native_options.
-            //
-            // The justification for FallbackEgl over PreferEgl is at https://github.com/emilk/egui/pull/2526#issuecomment-1400229576 .
-            .with_preference(glutin_winit::ApiPreference::FallbackEgl)
+            .with_preference(native_options.gl_api_preference)
             .with_window_attributes(Some(egui_winit::create_winit_window_attributes(
                 egui_ctx,
                 event_loop,
@@ -455,26 +607,50 @@ impl GlutinWindowContext {
         let (window, gl_config) = {
             profiling::scope!("DisplayBuilder::build");
 
-            display_builder
-                .build(
+            let no_configs_error = |err: Box<dyn std::error::Error + Send + Sync>| {
+                crate::Error::NoGlutinConfigs(
+                    format!("{:?}", config_template_builder.clone().build()),
+                    err,
+                )
+            };
+
+            // The picker has to return a config, so when the user's picker rejects all of them
+            // we hand back the best ranked one and bail out after the build.
+            let mut rejected = false;
+            let build_result = catch_no_configs(|| {
+                display_builder.build(
                     event_loop,
                     config_template_builder.clone(),
-                    |mut config_iterator| {
-                        let config = config_iterator.next().expect(
-                            "failed to find a matching configuration for creating glutin config",
-                        );
-                        log::debug!(
-                            "using the first config from config picker closure. config: {config:?}"
-                        );
-                        config
+                    |config_iterator| match pick_config(config_iterator.collect(), native_options) {
+                        Ok(config) => config,
+                        Err(best) => {
+                            rejected = true;
+                            best
+                        }
                     },
                 )
-                .map_err(|e| crate::Error::NoGlutinConfigs(config_template_builder.build(), e))?
+            });
+
+            let Some(build_result) = build_result else {
+                return Err(no_configs_error("the display has no configs at all".into()));
+            };
+            let result = build_result.map_err(|err| no_configs_error(send_sync_error(err)))?;
+            if rejected {
+                return Err(no_configs_error(
+                    "no config was picked from the available ones".into(),
+                ));
+            }
+            result
         };
         if let Some(window) = &window {
             egui_winit::apply_viewport_builder_to_window(egui_ctx, window, &viewport_builder);
         }
 
+        let transparent = native_options.viewport.transparent.unwrap_or(false)
+            && gl_config.supports_transparency() == Some(true)
+            && gl_config.alpha_size() > 0;
+        log::debug!("transparent framebuffer: {transparent}");
+
         let gl_display = gl_config.display();
         log::debug!(
             "successfully created GL Display with version: {} and supported features: {:?}",
@@ -488,33 +664,56 @@ impl GlutinWindowContext {
         });
         log::debug!("creating gl context using raw window handle: {glutin_raw_window_handle:?}");
 
-        // create gl context. if core context cannot be created, try gl es context as fallback.
-        let context_attributes =
-            glutin::context::ContextAttributesBuilder::new().build(glutin_raw_window_handle);
-        let fallback_context_attributes = glutin::context::ContextAttributesBuilder::new()
-            .with_context_api(glutin::context::ContextApi::Gles(None))
-            .build(glutin_raw_window_handle);
-
-        let gl_context_result = unsafe {
-            profiling::scope!("create_context");
-            gl_config
-                .display()
-                .create_context(&gl_config, &context_attributes)
+        let build_context_attributes = |context_api: Option<glutin::context::ContextApi>| {
+            let mut builder = glutin::context::ContextAttributesBuilder::new()
+                .with_debug(native_options.gl_debug)
+                .with_robustness(native_options.gl_robustness);
+            if let Some(context_api) = context_api {
+                builder = builder.with_context_api(context_api);
+            }
+            if let Some(profile) = native_options.gl_profile {
+                builder = builder.with_profile(profile);
+            }
+            builder.build(glutin_raw_window_handle)
         };
 
-        let gl_context = match gl_context_result {
-            Ok(it) => it,
-            Err(err) => {
-                log::warn!(
-                    "Failed to create context using default context attributes {context_attributes:?} due to error: {err}"
-                );
-                log::debug!(
-                    "Retrying with fallback context attributes: {fallback_context_attributes:?}"
-                );
-                unsafe {
-                    gl_config
-                        .display()
-                        .create_context(&gl_config, &fallback_context_attributes)?
+        let gl_context = if let Some(context_api) = native_options.gl_context_api {
+            // The user asked for something specific, so don't fall back to anything else.
+            let context_attributes = build_context_attributes(Some(context_api));
+            log::debug!("creating context with requested attributes: {context_attributes:?}");
+            unsafe {
+                profiling::scope!("create_context");
+                gl_config
+                    .display()
+                    .create_context(&gl_config, &context_attributes)?
+            }
+        } else {
+            // create gl context. if core context cannot be created, try gl es context as fallback.
+            let context_attributes = build_context_attributes(None);
+            let fallback_context_attributes =
+                build_context_attributes(Some(glutin::context::ContextApi::Gles(None)));
+
+            let gl_context_result = unsafe {
+                profiling::scope!("create_context");
+                gl_config
+                    .display()
+                    .create_context(&gl_config, &context_attributes)
+            };
+
+            match gl_context_result {
+                Ok(it) => it,
+                Err(err) => {
+                    log::warn!(
+                        "Failed to create context using default context attributes {context_attributes:?} due to error: {err}"
+                    );
+                    log::debug!(
+                        "Retrying with fallback context attributes: {fallback_context_attributes:?}"
+                    );
+                    unsafe {
+                        gl_config
+                            .display()
+                            .create_context(&gl_config, &fallback_context_attributes)?
+                    }
                 }
             }
         };
@@ -536,6 +735,7 @@ impl GlutinWindowContext {
                 ids: ViewportIdPair::ROOT,
                 class: ViewportClass::Root,
                 builder: viewport_builder,
+                initial_placement: None,
                 deferred_commands: vec![],
                 info,
                 actions_requested: Default::default(),
@@ -560,8 +760,16 @@ impl GlutinWindowContext {
             viewports,
             viewport_from_window,
             max_texture_side: None,
+            textures: TextureMirror::new(
+                native_options.gl_robustness
+                    == glutin::context::Robustness::RobustLoseContextOnReset,
+            ),
+            transparent,
             window_from_viewport,
             focused_viewport: Some(ViewportId::ROOT),
+            window_settings: Default::default(),
+            cursor_position: None,
+            activation_tokens: Default::default(),
         };
 
         slf.initialize_window(ViewportId::ROOT, event_loop)?;
@@ -602,23 +810,32 @@ impl GlutinWindowContext {
             window
         } else {
             log::debug!("Creating a window for viewport {viewport_id:?}");
-            let window_attributes = egui_winit::create_winit_window_attributes(
+            let builder = match &viewport.initial_placement {
+                Some(placement) => placement.apply(viewport.builder.clone()),
+                None => viewport.builder.clone(),
+            };
+            let mut window_attributes = egui_winit::create_winit_window_attributes(
                 &self.egui_ctx,
                 event_loop,
-                viewport.builder.clone(),
+                builder.clone(),
             );
-            if window_attributes.transparent()
-                && self.gl_config.supports_transparency() == Some(false)
-            {
-                log::error!("Cannot create transparent window: the GL config does not support it");
+            let activation_token = if viewport_id == ViewportId::ROOT {
+                monitor::startup_activation_token(event_loop)
+            } else {
+                self.activation_tokens.remove(&viewport.ids.parent)
+            };
+            if let Some(token) = activation_token {
+                window_attributes = monitor::with_activation_token(window_attributes, token);
+            }
+            if window_attributes.transparent() && !self.transparent {
+                log::warn!(
+                    "Cannot create transparent window: the GL config does not support it. \
+                     See IntegrationInfo::transparent"
+                );
             }
             let window =
                 glutin_winit::finalize_window(event_loop, window_attributes, &self.gl_config)?;
-            egui_winit::apply_viewport_builder_to_window(
-                &self.egui_ctx,
-                &window,
-                &viewport.builder,
-            );
+            egui_winit::apply_viewport_builder_to_window(&self.egui_ctx, &window, &builder);
 
             egui_winit::update_viewport_info(&mut viewport.info, &self.egui_ctx, &window, true);
             viewport.window.insert(Arc::new(window))
@@ -734,6 +951,34 @@ impl GlutinWindowContext {
         }
     }
 
+    /// Change the swap interval of all current and future surfaces.
+    fn set_vsync(&mut self, vsync: bool) {
+        let swap_interval = swap_interval(vsync);
+        if swap_interval == self.swap_interval {
+            return;
+        }
+        profiling::function_scope!();
+        log::debug!("Setting vsync to {vsync}");
+        self.swap_interval = swap_interval;
+
+        for viewport in self.viewports.values() {
+            let Some(gl_surface) = &viewport.gl_surface else {
+                continue;
+            };
+            change_gl_context(
+                &mut self.current_gl_context,
+                &mut self.not_current_gl_context,
+                gl_surface,
+            );
+            let Some(current_gl_context) = &self.current_gl_context else {
+                continue;
+            };
+            if let Err(err) = gl_surface.set_swap_interval(current_gl_context, swap_interval) {
+                log::warn!("Failed to set swap interval due to error: {err}");
+            }
+        }
+    }
+
     fn get_proc_address(&self, addr: &std::ffi::CStr) -> *const std::ffi::c_void {
         self.gl_config.display().get_proc_address(addr)
     }
@@ -742,6 +987,20 @@ impl GlutinWindowContext {
         &mut self,
         viewport_output: &ViewportIdMap<ViewportOutput>,
     ) {
+        // Remember where closed windows were, in case they are opened again:
+        for (viewport_id, viewport) in &self.viewports {
+            if !viewport_output.contains_key(viewport_id)
+                && let Some(window) = &viewport.window
+                && *viewport_id != ViewportId::ROOT
+                && epi::persist_viewport_window(&self.egui_ctx, *viewport_id)
+            {
+                self.window_settings.insert(
+                    *viewport_id,
+                    SavedWindow::from_window(self.egui_ctx.zoom_factor(), window),
+                );
+            }
+        }
+
         // GC old viewports
         self.viewports
             .retain(|id, _| viewport_output.contains_key(id));
@@ -751,6 +1010,65 @@ impl GlutinWindowContext {
             .retain(|id, _| viewport_output.contains_key(id));
     }
 
+    /// Keep track of what we need to know to place new windows.
+    fn on_window_event(&mut self, window_id: WindowId, event: &winit::event::WindowEvent) {
+        use winit::event::WindowEvent;
+
+        let Some(viewport_id) = self.viewport_from_window.get(&window_id).copied() else {
+            return;
+        };
+        let Some(window) = self
+            .viewports
+            .get(&viewport_id)
+            .and_then(|viewport| viewport.window.as_ref())
+        else {
+            return;
+        };
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                // Not known on Wayland, but there we can't position windows anyway.
+                if let Ok(inner_position) = window.inner_position() {
+                    self.cursor_position = Some(winit::dpi::PhysicalPosition::new(
+                        inner_position.x as f64 + position.x,
+                        inner_position.y as f64 + position.y,
+                    ));
+                }
+            }
+            WindowEvent::Focused(true) if !self.activation_tokens.contains_key(&viewport_id) => {
+                // Child windows are usually opened from the focused window, so have a token ready.
+                monitor::request_activation_token(window);
+            }
+            WindowEvent::ActivationTokenDone { token, .. } => {
+                self.activation_tokens.insert(viewport_id, token.clone());
+            }
+            _ => {}
+        }
+    }
+
+    /// The windows of all viewports except the root, for [`EpiIntegration::save`].
+    fn persisted_viewports(&self) -> Vec<PersistedViewport> {
+        let open = self
+            .viewports
+            .iter()
+            .filter(|(id, _)| {
+                **id != ViewportId::ROOT && epi::persist_viewport_window(&self.egui_ctx, **id)
+            })
+            .filter_map(|(id, viewport)| {
+                let window = viewport.window.as_deref()?;
+                Some((
+                    *id,
+                    SavedWindow::from_window(self.egui_ctx.zoom_factor(), window),
+                ))
+            });
+        let closed = self
+            .window_settings
+            .iter()
+            .filter(|(id, _)| !self.viewports.contains_key(id))
+            .map(|(id, saved_window)| (*id, saved_window.clone()))
+            .collect();
+        crate::epi_native::persisted_viewports(open, &closed)
+    }
+
     fn handle_viewport_output(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -774,13 +1092,33 @@ impl GlutinWindowContext {
             let ids = ViewportIdPair::from_self_and_parent(viewport_id, parent);
 
             let viewport = initialize_or_update_viewport(
+                egui_ctx,
+                event_loop,
                 &mut self.viewports,
+                &mut self.window_settings,
+                self.cursor_position,
                 ids,
                 class,
                 builder,
                 viewport_ui_cb,
             );
 
+            if viewport_id == ViewportId::ROOT {
+                // egui doesn't keep the root builder in sync with these, but the app icon reads it.
+                // The builders of other viewports come from the app, so they must not change here.
+                for command in &commands {
+                    match command {
+                        egui::viewport::ViewportCommand::Title(title) => {
+                            viewport.builder.title = Some(title.clone())
+                        }
+                        egui::viewport::ViewportCommand::Icon(icon) => {
+                            viewport.builder.icon = icon.clone()
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
             if let Some(window) = &viewport.window {
                 let old_inner_size = window.inner_size();
 
@@ -824,9 +1162,21 @@ impl<'app> GlowWinitApp<'app> {
             native_options,
             running: None,
             app_creator: Some(app_creator),
+            #[cfg(unix)]
+            single_instance: None,
         }
     }
 
+    /// Listen for other instances of the app once it's running.
+    #[cfg(unix)]
+    pub fn with_single_instance(
+        mut self,
+        single_instance: Option<crate::single_instance::SingleInstance>,
+    ) -> Self {
+        self.single_instance = single_instance;
+        self
+    }
+
     #[expect(unsafe_code)]
     fn create_glutin_windowed_context(
         egui_ctx: &egui::Context,
@@ -841,7 +1191,7 @@ impl<'app> GlowWinitApp<'app> {
             egui_ctx.zoom_factor(),
             event_loop,
             native_options,
-            window_settings,
+            window_settings.clone(),
         )
         .with_visible(false); // Start hidden until we render the first frame to fix white flash on startup (https://github.com/emilk/egui/pull/3631)
 
@@ -851,31 +1201,134 @@ impl<'app> GlowWinitApp<'app> {
 
         // Creates the window - must come before we create our glow context
         glutin_window_context.initialize_window(ViewportId::ROOT, event_loop)?;
+        glutin_window_context.window_settings = load_viewport_window_settings(storage);
 
         {
             let viewport = &glutin_window_context.viewports[&ViewportId::ROOT];
             let window = viewport.window.as_ref().unwrap(); // Can't fail - we just called `initialize_all_viewports`
-            apply_window_settings(window, window_settings);
+            apply_window_settings(window, window_settings.as_ref());
         }
 
-        let gl = unsafe {
+        let painter = Self::create_painter(&glutin_window_context, native_options)?;
+
+        Ok((glutin_window_context, painter))
+    }
+
+    #[expect(unsafe_code)]
+    fn create_painter(
+        glutin_window_context: &GlutinWindowContext,
+        native_options: &NativeOptions,
+    ) -> Result<egui_glow::Painter, crate::Error> {
+        let mut gl = unsafe {
             profiling::scope!("glow::Context::from_loader_function");
-            Arc::new(glow::Context::from_loader_function(|s| {
+            glow::Context::from_loader_function(|s| {
                 let s = std::ffi::CString::new(s)
                     .expect("failed to construct C string from string for gl proc address");
 
                 glutin_window_context.get_proc_address(&s)
-            }))
+            })
         };
 
-        let painter = egui_glow::Painter::new(
+        if native_options.gl_debug {
+            use glow::HasContext as _;
+
+            if gl.supports_debug() {
+                unsafe {
+                    gl.enable(glow::DEBUG_OUTPUT);
+                    gl.enable(glow::DEBUG_OUTPUT_SYNCHRONOUS);
+                    gl.debug_message_callback(log_gl_debug_message);
+                }
+            } else {
+                log::warn!("A GL debug context was requested, but KHR_debug is not supported");
+            }
+        }
+        let gl = Arc::new(gl);
+
+        Ok(egui_glow::Painter::new(
             gl,
             "",
             native_options.shader_version,
             native_options.dithering,
-        )?;
+        )?)
+    }
 
-        Ok((glutin_window_context, painter))
+    /// Replace a lost GL context (e.g. after a GPU reset) with a fresh one.
+    ///
+    /// All windows are recreated, egui's textures are re-uploaded,
+    /// and the app gets a chance to rebuild its own GL resources via [`App::on_gl_context_recreated`].
+    #[expect(unsafe_code)]
+    fn recreate_gl_context(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+    ) -> Result<EventResult, crate::Error> {
+        profiling::function_scope!();
+
+        let Some(running) = &mut self.running else {
+            return Ok(EventResult::Wait);
+        };
+
+        // Must happen while the old context is still current,
+        // so we don't delete objects of the new context by accident.
+        running.painter.borrow_mut().destroy();
+
+        let mut old_glutin = running.glutin.borrow_mut();
+        let egui_ctx = running.integration.egui_ctx.clone();
+        let root_builder = old_glutin
+            .viewport(ViewportId::ROOT)
+            .builder
+            .clone()
+            .with_visible(true); // We started hidden, but have been painting since.
+
+        let mut glutin = unsafe {
+            GlutinWindowContext::new(&egui_ctx, root_builder, &self.native_options, event_loop)?
+        };
+
+        // Keep the other viewports around; their windows are recreated below with the new config.
+        for (viewport_id, mut viewport) in old_glutin.viewports.drain() {
+            if viewport_id != ViewportId::ROOT {
+                viewport.window = None;
+                viewport.egui_winit = None;
+                viewport.gl_surface = None;
+                glutin.viewports.insert(viewport_id, viewport);
+            }
+        }
+        glutin.textures = std::mem::take(&mut old_glutin.textures);
+        glutin.window_settings = std::mem::take(&mut old_glutin.window_settings);
+        glutin.initialize_all_windows(event_loop);
+
+        let mut painter = Self::create_painter(&glutin, &self.native_options)?;
+        let gl = painter.gl().clone();
+
+        let max_texture_side = painter.max_texture_side();
+        glutin.max_texture_side = Some(max_texture_side);
+        for viewport in glutin.viewports.values_mut() {
+            if let Some(egui_winit) = viewport.egui_winit.as_mut() {
+                egui_winit.set_max_texture_side(max_texture_side);
+            }
+        }
+
+        for (id, delta) in glutin.textures.full_deltas().into_iter().flatten() {
+            painter.set_texture(*id, delta);
+        }
+
+        {
+            let window = glutin.window(ViewportId::ROOT);
+            let frame = &mut running.integration.frame;
+            frame.gl = Some(gl.clone());
+            frame.info.transparent = glutin.transparent;
+            frame.raw_display_handle = window.display_handle().map(|h| h.as_raw());
+            frame.raw_window_handle = window.window_handle().map(|h| h.as_raw());
+        }
+        let window_id = glutin.window(ViewportId::ROOT).id();
+
+        // Swap in place, so the immediate viewport renderer and `glow_register_native_texture` keep working.
+        *old_glutin = glutin;
+        drop(old_glutin);
+        *running.painter.borrow_mut() = painter;
+
+        running.app.on_gl_context_recreated(Some(&gl));
+
+        Ok(EventResult::RepaintNow(window_id))
     }
 
     fn init_run_state(
@@ -884,8 +1337,9 @@ impl<'app> GlowWinitApp<'app> {
     ) -> Result<&mut GlowWinitRunning<'app>, crate::Error> {
         profiling::function_scope!();
 
-        let storage = if let Some(file) = &self.native_options.persistence_path {
-            create_storage_with_file(file)
+        let mut storage_issues = Vec::new();
+        let mut storage = if let Some(file) = &self.native_options.persistence_path {
+            create_storage_with_file(file, &self.native_options, &mut storage_issues)
         } else {
             create_storage(
                 self.native_options
@@ -893,9 +1347,19 @@ impl<'app> GlowWinitApp<'app> {
                     .app_id
                     .as_ref()
                     .unwrap_or(&self.app_name),
+                &self.native_options,
+                &mut storage_issues,
             )
         };
 
+        #[cfg(feature = "persistence")]
+        if let Some(storage) = storage.as_deref_mut() {
+            storage_issues.extend(crate::migration::prepare_storage(
+                storage,
+                &self.native_options.app_migrations,
+            ));
+        }
+
         let egui_ctx = create_egui_context(storage.as_deref());
 
         let (mut glutin, painter) = Self::create_glutin_windowed_context(
@@ -916,7 +1380,7 @@ impl<'app> GlowWinitApp<'app> {
 
         let painter = Rc::new(RefCell::new(painter));
 
-        let integration = EpiIntegration::new(
+        let mut integration = EpiIntegration::new(
             egui_ctx,
             &glutin.window(ViewportId::ROOT),
             &self.app_name,
@@ -928,6 +1392,27 @@ impl<'app> GlowWinitApp<'app> {
                 move |native| painter.borrow_mut().register_native_texture(native)
             })),
         );
+        integration.frame.info.transparent = glutin.transparent;
+
+        #[cfg(unix)]
+        if let Some(single_instance) = &mut self.single_instance {
+            let egui_ctx = integration.egui_ctx.clone();
+            integration.other_instances =
+                Some(single_instance.listen(move || egui_ctx.request_repaint()));
+        }
+
+        #[cfg(feature = "input_recording")]
+        {
+            use crate::input_recording::{InputRecorder, InputReplay};
+            if let Some(path) = &self.native_options.replay_input {
+                integration.input_replay =
+                    Some(InputReplay::load(path).map_err(crate::Error::InputRecording)?);
+            }
+            if let Some(path) = &self.native_options.record_input {
+                integration.input_recorder =
+                    Some(InputRecorder::new(path).map_err(crate::Error::InputRecording)?);
+            }
+        }
 
         {
             let event_loop_proxy = self.repaint_proxy.clone();
@@ -986,6 +1471,7 @@ impl<'app> GlowWinitApp<'app> {
                 egui_ctx: integration.egui_ctx.clone(),
                 integration_info: integration.frame.info().clone(),
                 storage: integration.frame.storage(),
+                storage_issues,
                 gl: Some(gl),
                 get_proc_address: Some(&get_proc_address),
                 raw_display_handle: window.display_handle().map(|h| h.as_raw()),
@@ -997,37 +1483,166 @@ impl<'app> GlowWinitApp<'app> {
 
         let glutin = Rc::new(RefCell::new(glutin));
 
-        {
+        let immediate_renderer = {
             // Create weak pointers so that we don't keep
             // state alive for too long.
             let glutin = Rc::downgrade(&glutin);
             let painter = Rc::downgrade(&painter);
             let beginning = integration.beginning;
 
-            egui::Context::set_immediate_viewport_renderer(move |egui_ctx, immediate_viewport| {
-                if let (Some(glutin), Some(painter)) = (glutin.upgrade(), painter.upgrade()) {
-                    render_immediate_viewport(
-                        egui_ctx,
-                        &glutin,
-                        &painter,
-                        beginning,
-                        immediate_viewport,
-                    );
-                } else {
-                    log::warn!("render_sync_callback called after window closed");
-                }
-            });
-        }
+            immediate_viewport::register(
+                &integration.egui_ctx,
+                move |egui_ctx, immediate_viewport| {
+                    if let (Some(glutin), Some(painter)) = (glutin.upgrade(), painter.upgrade()) {
+                        render_immediate_viewport(
+                            egui_ctx,
+                            &glutin,
+                            &painter,
+                            beginning,
+                            immediate_viewport,
+                        );
+                    } else {
+                        log::warn!("render_sync_callback called after window closed");
+                    }
+                },
+            )
+        };
 
         Ok(self.running.insert(GlowWinitRunning {
             glutin,
             painter,
             integration,
             app,
+            _immediate_renderer: immediate_renderer,
         }))
     }
 }
 
+/// [`glutin_winit::DisplayBuilder::build`] fails with a non-[`Send`] error,
+/// which [`crate::Error`] can't hold, so get at the [`glutin`] or [`winit`] error inside.
+fn send_sync_error(err: Box<dyn std::error::Error>) -> Box<dyn std::error::Error + Send + Sync> {
+    match err.downcast::<glutin::error::Error>() {
+        Ok(err) => err,
+        Err(err) => match err.downcast::<winit::error::OsError>() {
+            Ok(err) => err,
+            Err(err) => err.to_string().into(),
+        },
+    }
+}
+
+/// Raised by [`pick_config`] when there are no configs, and caught by [`catch_no_configs`].
+struct NoConfigs;
+
+/// Rank the configs, and let [`NativeOptions::gl_config_picker`] pick one.
+///
+/// If the picker picks none, returns the best ranked one as the error,
+/// since [`glutin_winit::DisplayBuilder::build`] needs a config either way.
+/// Without any configs there is nothing to return, so this unwinds with [`NoConfigs`].
+fn pick_config(
+    mut configs: Vec<glutin::config::Config>,
+    native_options: &NativeOptions,
+) -> Result<glutin::config::Config, glutin::config::Config> {
+    rank_configs(&mut configs, native_options);
+    let Some(best) = configs.first().cloned() else {
+        // Unlike `panic!`, this doesn't run the panic hook, so nothing is reported as a crash.
+        std::panic::resume_unwind(Box::new(NoConfigs));
+    };
+    let config = match &native_options.gl_config_picker {
+        Some(picker) => picker(configs),
+        None => Some(best.clone()),
+    };
+    match config {
+        Some(config) => {
+            log::debug!("picked config: {config:?}");
+            Ok(config)
+        }
+        None => Err(best),
+    }
+}
+
+/// Run `build`, which calls [`pick_config`], and return `None` if there were no configs.
+///
+/// This can only catch [`NoConfigs`] if panics unwind, so with `panic = "abort"`
+/// a display without configs still aborts.
+fn catch_no_configs<T>(build: impl FnOnce() -> T) -> Option<T> {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(build)) {
+        Ok(result) => Some(result),
+        Err(payload) if payload.is::<NoConfigs>() => None,
+        Err(payload) => std::panic::resume_unwind(payload),
+    }
+}
+
+/// Sort the configs so the one best matching the [`NativeOptions`] comes first.
+///
+/// In order of importance we look at transparency, multisampling, and sRGB.
+/// egui does its own gamma handling, so it prefers framebuffers that are not sRGB-capable.
+fn rank_configs(configs: &mut [glutin::config::Config], native_options: &NativeOptions) {
+    let transparent = native_options.viewport.transparent.unwrap_or(false);
+    let samples = native_options.multisampling;
+
+    configs.sort_by_key(|config| {
+        let transparency_mismatch = transparent
+            && (config.supports_transparency() != Some(true) || config.alpha_size() == 0);
+        let config_samples = u16::from(config.num_samples());
+        // Exact match first, then more samples than requested (closest first), then fewer.
+        let samples_rank = if config_samples == samples {
+            (0, 0)
+        } else if config_samples > samples {
+            (1, config_samples - samples)
+        } else {
+            (2, samples - config_samples)
+        };
+        let srgb_mismatch = config.srgb_capable() != native_options.srgb_framebuffer;
+        (transparency_mismatch, samples_rank, srgb_mismatch)
+    });
+
+    if let Some(best) = configs.first() {
+        if transparent && best.supports_transparency() != Some(true) {
+            log::warn!("No GL config supports transparency");
+        }
+        if u16::from(best.num_samples()) != samples {
+            log::warn!(
+                "No GL config with {samples}x multisampling, using {}x",
+                best.num_samples()
+            );
+        }
+    }
+}
+
+fn swap_interval(vsync: bool) -> glutin::surface::SwapInterval {
+    if vsync {
+        glutin::surface::SwapInterval::Wait(NonZeroU32::MIN)
+    } else {
+        glutin::surface::SwapInterval::DontWait
+    }
+}
+
+/// The color to clear the framebuffer with, given [`App::clear_color`].
+///
+/// That is already premultiplied, as compositors expect. Without transparency the alpha
+/// is meaningless, so we make sure nothing shines through.
+fn framebuffer_clear_color([r, g, b, a]: [f32; 4], transparent: bool) -> [f32; 4] {
+    if transparent {
+        [r, g, b, a]
+    } else {
+        [r, g, b, 1.0]
+    }
+}
+
+/// Forwards `KHR_debug` messages to [`log`].
+fn log_gl_debug_message(source: u32, message_type: u32, id: u32, severity: u32, message: &str) {
+    let level = match severity {
+        glow::DEBUG_SEVERITY_HIGH => log::Level::Error,
+        glow::DEBUG_SEVERITY_MEDIUM => log::Level::Warn,
+        glow::DEBUG_SEVERITY_LOW => log::Level::Info,
+        _ => log::Level::Debug,
+    };
+    log::log!(
+        level,
+        "GL debug message (source: {source:#x}, type: {message_type:#x}, id: {id}): {message}"
+    );
+}
+
 fn change_gl_context(
     current_gl_context: &mut Option<glutin::context::PossiblyCurrentContext>,
     not_current_gl_context: &mut Option<glutin::context::NotCurrentContext>,
@@ -1064,13 +1679,18 @@ fn change_gl_context(
     *current_gl_context = Some(not_current.make_current(gl_surface).unwrap());
 }
 
-fn initialize_or_update_viewport(
-    viewports: &mut ViewportIdMap<Viewport>,
+#[expect(clippy::too_many_arguments)]
+fn initialize_or_update_viewport<'a>(
+    egui_ctx: &egui::Context,
+    event_loop: &ActiveEventLoop,
+    viewports: &'a mut ViewportIdMap<Viewport>,
+    window_settings: &mut ViewportIdMap<SavedWindow>,
+    cursor_position: Option<winit::dpi::PhysicalPosition<f64>>,
     ids: ViewportIdPair,
     class: ViewportClass,
     mut builder: ViewportBuilder,
     viewport_ui_cb: Option<Arc<dyn Fn(&egui::Context) + Send + Sync>>,
-) -> &mut Viewport {
+) -> &'a mut Viewport {
     profiling::function_scope!();
 
     if builder.icon.is_none() {
@@ -1080,14 +1700,47 @@ fn initialize_or_update_viewport(
             .and_then(|vp| vp.builder.icon.clone());
     }
 
+    // New viewport: restore where it was the last time it was open, or center it:
+    let mut initial_placement = None;
+    #[cfg(not(target_os = "ios"))]
+    if ids.this != ViewportId::ROOT && !viewports.contains_key(&ids.this) {
+        let saved_window = epi::persist_viewport_window(egui_ctx, ids.this)
+            .then(|| window_settings.remove(&ids.this))
+            .flatten();
+        let placed = if let Some(mut saved_window) = saved_window {
+            Some(crate::epi_native::restore_window_settings(
+                egui_ctx.zoom_factor(),
+                event_loop,
+                builder.clone(),
+                &mut saved_window,
+            ))
+        } else if let Some(centering) = epi::viewport_centering(egui_ctx, ids.this) {
+            let parent = viewports
+                .get(&ids.parent)
+                .and_then(|parent| parent.window.as_deref());
+            Some(centering.center(
+                egui_ctx.zoom_factor(),
+                event_loop,
+                builder.clone(),
+                parent,
+                cursor_position,
+            ))
+        } else {
+            None
+        };
+        initial_placement = placed.as_ref().map(InitialPlacement::new);
+    }
+
     match viewports.entry(ids.this) {
         std::collections::hash_map::Entry::Vacant(entry) => {
             // New viewport:
             log::debug!("Creating new viewport {:?} ({:?})", ids.this, builder.title);
+
             entry.insert(Viewport {
                 ids,
                 class,
                 builder,
+                initial_placement,
                 deferred_commands: vec![],
                 info: Default::default(),
                 actions_requested: Default::default(),
@@ -1148,15 +1801,19 @@ fn render_immediate_viewport(
     {
         let mut glutin = glutin.borrow_mut();
 
-        initialize_or_update_viewport(
-            &mut glutin.viewports,
-            ids,
-            ViewportClass::Immediate,
-            builder,
-            None,
-        );
-
         let ret = event_loop_context::with_current_event_loop(|event_loop| {
+            let glutin = &mut *glutin;
+            initialize_or_update_viewport(
+                egui_ctx,
+                event_loop,
+                &mut glutin.viewports,
+                &mut glutin.window_settings,
+                glutin.cursor_position,
+                ids,
+                ViewportClass::Immediate,
+                builder,
+                None,
+            );
             glutin.initialize_window(viewport_id, event_loop)
         });
 
@@ -1213,6 +1870,7 @@ fn render_immediate_viewport(
         current_gl_context,
         not_current_gl_context,
         viewports,
+        textures,
         ..
     } = &mut *glutin;
 
@@ -1250,6 +1908,7 @@ fn render_immediate_viewport(
         [0.0, 0.0, 0.0, 0.0],
     );
 
+    textures.apply(&textures_delta);
     painter.borrow_mut().paint_and_update_textures(
         screen_size_in_pixels,
         pixels_per_point,
@@ -1270,3 +1929,53 @@ fn render_immediate_viewport(
         glutin.handle_viewport_output(event_loop, egui_ctx, &viewport_output);
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_configs_error_keeps_the_glutin_error() {
+        let glutin_error = glutin::error::Error::from(glutin::error::ErrorKind::NotFound);
+        let err =
+            crate::Error::NoGlutinConfigs(String::new(), send_sync_error(Box::new(glutin_error)));
+        let source = std::error::Error::source(&err).unwrap();
+        let source = source.downcast_ref::<glutin::error::Error>().unwrap();
+        assert_eq!(source.error_kind(), glutin::error::ErrorKind::NotFound);
+
+        let other = send_sync_error("something else".into());
+        assert_eq!(other.to_string(), "something else");
+    }
+
+    #[test]
+    fn default_clear_color_is_premultiplied_once() {
+        struct Blank;
+        impl App for Blank {
+            fn update(&mut self, _ctx: &egui::Context, _frame: &mut epi::Frame) {}
+        }
+
+        let clear_color = Blank.clear_color(&egui::Visuals::dark());
+        let premultiplied = egui::Color32::from_rgba_unmultiplied(12, 12, 12, 180);
+        assert_eq!(clear_color, premultiplied.to_normalized_gamma_f32());
+        assert_eq!(framebuffer_clear_color(clear_color, true), clear_color);
+
+        let [r, g, b, _] = clear_color;
+        assert_eq!(framebuffer_clear_color(clear_color, false), [r, g, b, 1.0]);
+    }
+
+    #[test]
+    fn no_configs_is_an_error_not_a_panic() {
+        let mut native_options = NativeOptions::default();
+        assert!(catch_no_configs(|| pick_config(Vec::new(), &native_options)).is_none());
+
+        native_options.gl_config_picker = Some(Box::new(|configs| configs.into_iter().next()));
+        assert!(catch_no_configs(|| pick_config(Vec::new(), &native_options)).is_none());
+    }
+
+    #[test]
+    fn catch_no_configs_lets_other_panics_through() {
+        assert_eq!(catch_no_configs(|| 1), Some(1));
+        let result = std::panic::catch_unwind(|| catch_no_configs(|| panic!("not about configs")));
+        assert!(result.is_err());
+    }
+}
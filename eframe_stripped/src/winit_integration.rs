@@ -2,7 +2,7 @@ use std::{sync::Arc, time::Instant};
 
 use egui::ViewportId;
 use winit::{
-    event_loop::ActiveEventLoop,
+    event_loop::{ActiveEventLoop, ControlFlow},
     window::{Window, WindowId},
 };
 
@@ -15,10 +15,26 @@ pub trait WinitApp {
 
     fn window_id_from_viewport_id(&self, id: ViewportId) -> Option<WindowId>;
 
+    /// Ask winit to send a [`winit::event::WindowEvent::RedrawRequested`] for the window.
+    ///
+    /// Returns `false` if there is no such window.
+    fn request_redraw(&self, window_id: WindowId) -> bool;
+
+    /// The frame rate limit requested by the app, if any.
+    fn max_fps(&self) -> Option<f32>;
+
+    /// Where to report the event loop state, once the app is running.
+    fn debug_overlay(&mut self) -> Option<&mut crate::debug_overlay::DebugOverlay>;
+
     fn save(&mut self);
 
     fn save_and_destroy(&mut self);
 
+    /// Close all windows and release the GL state, without saving or notifying the app.
+    ///
+    /// Used after a panic, when the app may be in an inconsistent state.
+    fn destroy(&mut self);
+
     fn run_ui_and_paint(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -44,6 +60,31 @@ pub trait WinitApp {
     ) -> Result<EventResult, crate::Error>;
 }
 
+/// The parts of [`ActiveEventLoop`] used to schedule repaints and exit.
+///
+/// Lets [`crate::winit_wrapper::WinitAppWrapper`] be tested without a real event loop.
+pub trait EventLoopControl {
+    fn set_control_flow(&self, control_flow: ControlFlow);
+
+    fn control_flow(&self) -> ControlFlow;
+
+    fn exit(&self);
+}
+
+impl EventLoopControl for ActiveEventLoop {
+    fn set_control_flow(&self, control_flow: ControlFlow) {
+        self.set_control_flow(control_flow);
+    }
+
+    fn control_flow(&self) -> ControlFlow {
+        self.control_flow()
+    }
+
+    fn exit(&self) {
+        self.exit();
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EventResult {
     Wait,
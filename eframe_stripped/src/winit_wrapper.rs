@@ -1,4 +1,4 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use ahash::HashMap;
 use winit::{
@@ -10,37 +10,49 @@ use winit::{
 use crate::{
     epi::UserEvent,
     event_loop_context,
-    winit_integration::{EventResult, WinitApp},
+    event_trace::EventTrace,
+    winit_integration::{EventLoopControl, EventResult, WinitApp},
 };
 
 /// Wraps a [`WinitApp`] to implement [`ApplicationHandler`]. This handles redrawing, exit states, and
 /// some events, but otherwise forwards events to the [`WinitApp`].
 pub struct WinitAppWrapper<T: WinitApp> {
     windows_next_repaint_times: HashMap<WindowId, Instant>,
+
+    /// When each window last started painting, used to enforce [`WinitApp::max_fps`].
+    windows_last_paint_times: HashMap<WindowId, Instant>,
     winit_app: T,
     pub(crate) return_result: Result<(), crate::Error>,
     run_and_return: bool,
+
+    /// See [`crate::NativeOptions::event_trace_path`].
+    trace: Option<EventTrace>,
 }
 
 impl<T: WinitApp> WinitAppWrapper<T> {
-    pub(crate) fn new(winit_app: T, run_and_return: bool) -> Self {
+    pub(crate) fn new(winit_app: T, run_and_return: bool, trace: Option<EventTrace>) -> Self {
         Self {
             windows_next_repaint_times: HashMap::default(),
+            windows_last_paint_times: HashMap::default(),
             winit_app,
             return_result: Ok(()),
             run_and_return,
+            trace,
         }
     }
 
     fn handle_event_result(
         &mut self,
-        event_loop: &ActiveEventLoop,
+        event_loop: &impl EventLoopControl,
         event_result: Result<EventResult, crate::Error>,
     ) {
         let mut exit = false;
         let mut save = false;
 
         log::trace!("event_result: {event_result:?}");
+        if let Some(trace) = &mut self.trace {
+            trace.event_result(&event_result);
+        }
 
         // let mut event_result = event_result;
 
@@ -63,22 +75,22 @@ impl<T: WinitApp> WinitAppWrapper<T> {
             EventResult::RepaintNow(window_id) => {
                 log::trace!("RepaintNow of {window_id:?}",);
                 self.windows_next_repaint_times
-                    .insert(window_id, Instant::now());
+                    .insert(window_id, self.limit_frame_rate(window_id, Instant::now()));
                 event_result
             }
             EventResult::RepaintNext(window_id) => {
                 log::trace!("RepaintNext of {window_id:?}",);
                 self.windows_next_repaint_times
-                    .insert(window_id, Instant::now());
+                    .insert(window_id, self.limit_frame_rate(window_id, Instant::now()));
                 event_result
             }
             EventResult::RepaintAt(window_id, repaint_time) => {
-                self.windows_next_repaint_times.insert(
-                    window_id,
-                    self.windows_next_repaint_times
-                        .get(&window_id)
-                        .map_or(repaint_time, |last| (*last).min(repaint_time)),
-                );
+                let repaint_time = self
+                    .windows_next_repaint_times
+                    .get(&window_id)
+                    .map_or(repaint_time, |last| (*last).min(repaint_time));
+                self.windows_next_repaint_times
+                    .insert(window_id, self.limit_frame_rate(window_id, repaint_time));
                 event_result
             }
             EventResult::Save => {
@@ -103,6 +115,9 @@ impl<T: WinitApp> WinitAppWrapper<T> {
         }
 
         if exit {
+            if let Some(trace) = &mut self.trace {
+                trace.exit();
+            }
             if self.run_and_return {
                 log::debug!("Asking to exit event loop…");
                 event_loop.exit();
@@ -119,7 +134,82 @@ impl<T: WinitApp> WinitAppWrapper<T> {
         self.check_redraw_requests(event_loop);
     }
 
-    fn check_redraw_requests(&mut self, event_loop: &ActiveEventLoop) {
+    /// Push `repaint_time` back so that `window_id` isn't painted more often than [`WinitApp::max_fps`].
+    ///
+    /// A limit so low that the frame time doesn't fit in a [`Duration`] or [`Instant`] is ignored.
+    fn limit_frame_rate(&self, window_id: WindowId, repaint_time: Instant) -> Instant {
+        let Some(max_fps) = self.winit_app.max_fps() else {
+            return repaint_time;
+        };
+        let Some(last_paint_time) = self.windows_last_paint_times.get(&window_id) else {
+            return repaint_time;
+        };
+        let Some(earliest) = Duration::try_from_secs_f32(1.0 / max_fps)
+            .ok()
+            .and_then(|frame_time| last_paint_time.checked_add(frame_time))
+        else {
+            return repaint_time;
+        };
+        repaint_time.max(earliest)
+    }
+
+    /// Run `f`, turning any panic into [`crate::Error::AppPanicked`].
+    ///
+    /// Unwinding through winit is not an option: it aborts on some platforms,
+    /// and leaves the thread-local event loop unusable for the next [`crate::run_native`].
+    fn catch_panic(&mut self, event_loop: &impl EventLoopControl, f: impl FnOnce(&mut Self)) {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&mut *self)));
+
+        if let Err(payload) = result {
+            let message = panic_message(payload.as_ref());
+            log::error!("Exiting because the app panicked: {message}");
+
+            self.winit_app.destroy();
+            self.windows_next_repaint_times.clear();
+            self.return_result = Err(crate::Error::AppPanicked(message));
+            if let Some(trace) = &mut self.trace {
+                trace.exit();
+            }
+            event_loop.exit();
+        }
+    }
+
+    /// Schedule a repaint for the user event, unless it is outdated.
+    fn handle_user_event(&mut self, event_loop: &impl EventLoopControl, event: UserEvent) {
+        let event_result = match event {
+            UserEvent::RequestRepaint {
+                when,
+                cumulative_pass_nr,
+                viewport_id,
+            } => {
+                let current_pass_nr = self
+                    .winit_app
+                    .egui_ctx()
+                    .map_or(0, |ctx| ctx.cumulative_pass_nr_for(viewport_id));
+                if current_pass_nr == cumulative_pass_nr
+                    || current_pass_nr == cumulative_pass_nr + 1
+                {
+                    log::trace!("UserEvent::RequestRepaint scheduling repaint at {when:?}");
+                    if let Some(window_id) = self.winit_app.window_id_from_viewport_id(viewport_id)
+                    {
+                        Ok(EventResult::RepaintAt(window_id, when))
+                    } else {
+                        Ok(EventResult::Wait)
+                    }
+                } else {
+                    log::trace!("Got outdated UserEvent::RequestRepaint");
+                    Ok(EventResult::Wait) // old request - we've already repainted
+                }
+            }
+            #[cfg(feature = "accesskit")]
+            UserEvent::AccessKitActionRequest(request) => {
+                self.winit_app.on_accesskit_event(request)
+            }
+        };
+        self.handle_event_result(event_loop, event_result);
+    }
+
+    fn check_redraw_requests(&mut self, event_loop: &impl EventLoopControl) {
         let now = Instant::now();
 
         self.windows_next_repaint_times
@@ -130,9 +220,11 @@ impl<T: WinitApp> WinitAppWrapper<T> {
 
                 event_loop.set_control_flow(ControlFlow::Poll);
 
-                if let Some(window) = self.winit_app.window(*window_id) {
+                if self.winit_app.request_redraw(*window_id) {
                     log::trace!("request_redraw for {window_id:?}");
-                    window.request_redraw();
+                    if let Some(trace) = &mut self.trace {
+                        trace.request_redraw(*window_id);
+                    }
                 } else {
                     log::trace!("No window found for {window_id:?}");
                 }
@@ -143,6 +235,19 @@ impl<T: WinitApp> WinitAppWrapper<T> {
         if let Some(next_repaint_time) = next_repaint_time {
             event_loop.set_control_flow(ControlFlow::WaitUntil(next_repaint_time));
         };
+
+        if let Some(trace) = &mut self.trace {
+            trace.control_flow(event_loop.control_flow());
+        }
+
+        if let Some(debug_overlay) = self.winit_app.debug_overlay() {
+            debug_overlay.set_event_loop_state(
+                event_loop.control_flow(),
+                self.windows_next_repaint_times
+                    .iter()
+                    .map(|(window_id, repaint_time)| (*window_id, *repaint_time)),
+            );
+        }
     }
 }
 
@@ -161,8 +266,13 @@ impl<T: WinitApp> ApplicationHandler<UserEvent> for WinitAppWrapper<T> {
 
         // Nb: Make sure this guard is dropped after this function returns.
         event_loop_context::with_event_loop_context(event_loop, move || {
-            let event_result = self.winit_app.resumed(event_loop);
-            self.handle_event_result(event_loop, event_result);
+            if let Some(trace) = &mut self.trace {
+                trace.callback("resumed", None, "");
+            }
+            self.catch_panic(event_loop, |slf| {
+                let event_result = slf.winit_app.resumed(event_loop);
+                slf.handle_event_result(event_loop, event_result);
+            });
         });
     }
 
@@ -198,38 +308,19 @@ impl<T: WinitApp> ApplicationHandler<UserEvent> for WinitAppWrapper<T> {
         });
 
         event_loop_context::with_event_loop_context(event_loop, move || {
-            let event_result = match event {
-                UserEvent::RequestRepaint {
-                    when,
-                    cumulative_pass_nr,
-                    viewport_id,
-                } => {
-                    let current_pass_nr = self
-                        .winit_app
-                        .egui_ctx()
-                        .map_or(0, |ctx| ctx.cumulative_pass_nr_for(viewport_id));
-                    if current_pass_nr == cumulative_pass_nr
-                        || current_pass_nr == cumulative_pass_nr + 1
-                    {
-                        log::trace!("UserEvent::RequestRepaint scheduling repaint at {when:?}");
-                        if let Some(window_id) =
-                            self.winit_app.window_id_from_viewport_id(viewport_id)
-                        {
-                            Ok(EventResult::RepaintAt(window_id, when))
-                        } else {
-                            Ok(EventResult::Wait)
-                        }
-                    } else {
-                        log::trace!("Got outdated UserEvent::RequestRepaint");
-                        Ok(EventResult::Wait) // old request - we've already repainted
+            if let Some(trace) = &mut self.trace {
+                let detail = match &event {
+                    UserEvent::RequestRepaint { viewport_id, .. } => {
+                        format!("RequestRepaint({viewport_id:?})")
                     }
-                }
-                #[cfg(feature = "accesskit")]
-                UserEvent::AccessKitActionRequest(request) => {
-                    self.winit_app.on_accesskit_event(request)
-                }
-            };
-            self.handle_event_result(event_loop, event_result);
+                    #[cfg(feature = "accesskit")]
+                    UserEvent::AccessKitActionRequest(_) => "AccessKitActionRequest".to_owned(),
+                };
+                trace.callback("user_event", None, &detail);
+            }
+            self.catch_panic(event_loop, |slf| {
+                slf.handle_user_event(event_loop, event);
+            });
         });
     }
 
@@ -251,18 +342,406 @@ impl<T: WinitApp> ApplicationHandler<UserEvent> for WinitAppWrapper<T> {
 
         // Nb: Make sure this guard is dropped after this function returns.
         event_loop_context::with_event_loop_context(event_loop, move || {
+            if let Some(trace) = &mut self.trace {
+                trace.callback(
+                    "window_event",
+                    Some(window_id),
+                    egui_winit::short_window_event_description(&event),
+                );
+            }
             if matches!(event, winit::event::WindowEvent::CloseRequested) {
+                if let Some(trace) = &mut self.trace {
+                    trace.exit();
+                }
                 event_loop.exit();
                 return;
             }
-            let event_result = match event {
-                winit::event::WindowEvent::RedrawRequested => {
-                    self.winit_app.run_ui_and_paint(event_loop, window_id)
-                }
-                _ => self.winit_app.window_event(event_loop, window_id, event),
-            };
+            self.catch_panic(event_loop, |slf| {
+                let event_result = match event {
+                    winit::event::WindowEvent::RedrawRequested => {
+                        slf.windows_last_paint_times
+                            .insert(window_id, Instant::now());
+                        slf.winit_app.run_ui_and_paint(event_loop, window_id)
+                    }
+                    _ => slf.winit_app.window_event(event_loop, window_id, event),
+                };
 
-            self.handle_event_result(event_loop, event_result);
+                slf.handle_event_result(event_loop, event_result);
+            });
         });
     }
 }
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        sync::Arc,
+    };
+
+    use egui::ViewportId;
+    use winit::window::Window;
+
+    use super::*;
+
+    const ROOT_WINDOW: WindowId = WindowId::dummy();
+
+    fn other_window() -> WindowId {
+        WindowId::from(u64::from(ROOT_WINDOW) + 1)
+    }
+
+    #[derive(Default)]
+    struct FakeEventLoop {
+        control_flow: Cell<ControlFlow>,
+        exited: Cell<bool>,
+    }
+
+    impl EventLoopControl for FakeEventLoop {
+        fn set_control_flow(&self, control_flow: ControlFlow) {
+            self.control_flow.set(control_flow);
+        }
+
+        fn control_flow(&self) -> ControlFlow {
+            self.control_flow.get()
+        }
+
+        fn exit(&self) {
+            self.exited.set(true);
+        }
+    }
+
+    /// Has a root window and [`other_window`], and records what the wrapper asks of it.
+    #[derive(Default)]
+    struct FakeApp {
+        egui_ctx: egui::Context,
+        max_fps: Option<f32>,
+        redraws: RefCell<Vec<WindowId>>,
+        saves: usize,
+    }
+
+    impl WinitApp for FakeApp {
+        fn egui_ctx(&self) -> Option<&egui::Context> {
+            Some(&self.egui_ctx)
+        }
+
+        fn window(&self, _window_id: WindowId) -> Option<Arc<Window>> {
+            None
+        }
+
+        fn window_id_from_viewport_id(&self, id: ViewportId) -> Option<WindowId> {
+            (id == ViewportId::ROOT).then_some(ROOT_WINDOW)
+        }
+
+        fn request_redraw(&self, window_id: WindowId) -> bool {
+            let known = window_id == ROOT_WINDOW || window_id == other_window();
+            if known {
+                self.redraws.borrow_mut().push(window_id);
+            }
+            known
+        }
+
+        fn max_fps(&self) -> Option<f32> {
+            self.max_fps
+        }
+
+        fn debug_overlay(&mut self) -> Option<&mut crate::debug_overlay::DebugOverlay> {
+            None
+        }
+
+        fn save(&mut self) {
+            self.saves += 1;
+        }
+
+        fn save_and_destroy(&mut self) {
+            unreachable!("only called when not returning from the event loop");
+        }
+
+        fn destroy(&mut self) {}
+
+        fn run_ui_and_paint(
+            &mut self,
+            _: &ActiveEventLoop,
+            _: WindowId,
+        ) -> Result<EventResult, crate::Error> {
+            unreachable!("needs a real event loop");
+        }
+
+        fn resumed(&mut self, _: &ActiveEventLoop) -> Result<EventResult, crate::Error> {
+            unreachable!("needs a real event loop");
+        }
+
+        fn window_event(
+            &mut self,
+            _: &ActiveEventLoop,
+            _: WindowId,
+            _: winit::event::WindowEvent,
+        ) -> Result<EventResult, crate::Error> {
+            unreachable!("needs a real event loop");
+        }
+    }
+
+    fn wrapper() -> WinitAppWrapper<FakeApp> {
+        WinitAppWrapper::new(FakeApp::default(), true, None)
+    }
+
+    fn redraws(wrapper: &WinitAppWrapper<FakeApp>) -> Vec<WindowId> {
+        wrapper.winit_app.redraws.borrow().clone()
+    }
+
+    #[test]
+    fn wait() {
+        let event_loop = FakeEventLoop::default();
+        event_loop.set_control_flow(ControlFlow::Poll);
+        let mut wrapper = wrapper();
+
+        wrapper.handle_event_result(&event_loop, Ok(EventResult::Wait));
+
+        assert_eq!(event_loop.control_flow(), ControlFlow::Wait);
+        assert!(redraws(&wrapper).is_empty());
+        assert!(!event_loop.exited.get());
+    }
+
+    #[test]
+    fn repaint_now() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+
+        wrapper.handle_event_result(&event_loop, Ok(EventResult::RepaintNow(ROOT_WINDOW)));
+
+        assert_eq!(redraws(&wrapper), [ROOT_WINDOW]);
+        assert_eq!(event_loop.control_flow(), ControlFlow::Poll);
+        assert!(wrapper.windows_next_repaint_times.is_empty());
+    }
+
+    #[test]
+    fn repaint_next() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+
+        wrapper.handle_event_result(&event_loop, Ok(EventResult::RepaintNext(ROOT_WINDOW)));
+
+        assert_eq!(redraws(&wrapper), [ROOT_WINDOW]);
+        assert_eq!(event_loop.control_flow(), ControlFlow::Poll);
+    }
+
+    #[test]
+    fn repaint_at_in_the_future_waits() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+        let when = Instant::now() + Duration::from_secs(10);
+
+        wrapper.handle_event_result(&event_loop, Ok(EventResult::RepaintAt(ROOT_WINDOW, when)));
+
+        assert!(redraws(&wrapper).is_empty());
+        assert_eq!(event_loop.control_flow(), ControlFlow::WaitUntil(when));
+    }
+
+    #[test]
+    fn repaint_at_keeps_the_earliest_time() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+        let now = Instant::now();
+        let soon = now + Duration::from_secs(10);
+
+        for when in [
+            now + Duration::from_secs(20),
+            soon,
+            now + Duration::from_secs(30),
+        ] {
+            wrapper.handle_event_result(&event_loop, Ok(EventResult::RepaintAt(ROOT_WINDOW, when)));
+        }
+
+        assert!(redraws(&wrapper).is_empty());
+        assert_eq!(event_loop.control_flow(), ControlFlow::WaitUntil(soon));
+    }
+
+    #[test]
+    fn repaint_at_in_the_past_redraws() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+
+        wrapper.handle_event_result(
+            &event_loop,
+            Ok(EventResult::RepaintAt(ROOT_WINDOW, Instant::now())),
+        );
+
+        assert_eq!(redraws(&wrapper), [ROOT_WINDOW]);
+        assert_eq!(event_loop.control_flow(), ControlFlow::Poll);
+    }
+
+    #[test]
+    fn due_repaint_does_not_hide_later_ones() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+        let later = Instant::now() + Duration::from_secs(10);
+
+        wrapper.handle_event_result(
+            &event_loop,
+            Ok(EventResult::RepaintAt(other_window(), later)),
+        );
+        wrapper.handle_event_result(&event_loop, Ok(EventResult::RepaintNow(ROOT_WINDOW)));
+
+        assert_eq!(redraws(&wrapper), [ROOT_WINDOW]);
+        assert_eq!(event_loop.control_flow(), ControlFlow::WaitUntil(later));
+    }
+
+    #[test]
+    fn repaint_of_unknown_window_is_dropped() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+        let unknown = WindowId::from(u64::from(ROOT_WINDOW) + 100);
+
+        wrapper.handle_event_result(&event_loop, Ok(EventResult::RepaintNow(unknown)));
+
+        assert!(redraws(&wrapper).is_empty());
+        assert!(wrapper.windows_next_repaint_times.is_empty());
+    }
+
+    #[test]
+    fn max_fps_delays_repaints() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+        wrapper.winit_app.max_fps = Some(8.0); // exactly 125 ms per frame
+        let last_paint = Instant::now();
+        wrapper
+            .windows_last_paint_times
+            .insert(ROOT_WINDOW, last_paint);
+
+        wrapper.handle_event_result(&event_loop, Ok(EventResult::RepaintNow(ROOT_WINDOW)));
+
+        assert!(redraws(&wrapper).is_empty());
+        assert_eq!(
+            event_loop.control_flow(),
+            ControlFlow::WaitUntil(last_paint + Duration::from_millis(125))
+        );
+    }
+
+    #[test]
+    fn limit_frame_rate_at_the_edges() {
+        let mut wrapper = wrapper();
+        let last_paint = Instant::now();
+        wrapper
+            .windows_last_paint_times
+            .insert(ROOT_WINDOW, last_paint);
+
+        // Too slow to represent: no limit instead of a panic.
+        for max_fps in [f32::MIN_POSITIVE, 1e-30, 1e-19] {
+            wrapper.winit_app.max_fps = Some(max_fps);
+            assert_eq!(
+                wrapper.limit_frame_rate(ROOT_WINDOW, last_paint),
+                last_paint
+            );
+        }
+
+        // Too fast to matter.
+        for max_fps in [f32::MAX, f32::INFINITY] {
+            wrapper.winit_app.max_fps = Some(max_fps);
+            assert_eq!(
+                wrapper.limit_frame_rate(ROOT_WINDOW, last_paint),
+                last_paint
+            );
+        }
+
+        // Windows that were never painted aren't limited.
+        wrapper.winit_app.max_fps = Some(1.0);
+        assert_eq!(
+            wrapper.limit_frame_rate(other_window(), last_paint),
+            last_paint
+        );
+        assert_eq!(
+            wrapper.limit_frame_rate(ROOT_WINDOW, last_paint),
+            last_paint + Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn save() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+
+        wrapper.handle_event_result(&event_loop, Ok(EventResult::Save));
+
+        assert_eq!(wrapper.winit_app.saves, 1);
+        assert!(!event_loop.exited.get());
+    }
+
+    #[test]
+    fn exit() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+
+        wrapper.handle_event_result(&event_loop, Ok(EventResult::Exit));
+
+        assert!(event_loop.exited.get());
+        assert!(wrapper.return_result.is_ok());
+    }
+
+    #[test]
+    fn error_exits_with_the_error() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+
+        wrapper.handle_event_result(
+            &event_loop,
+            Err(crate::Error::AppPanicked("oops".to_owned())),
+        );
+
+        assert!(event_loop.exited.get());
+        assert_eq!(
+            wrapper.return_result.map_err(|err| err.kind()),
+            Err(crate::ErrorKind::AppPanicked)
+        );
+    }
+
+    #[test]
+    fn panic_exits_with_app_panicked() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+
+        wrapper.catch_panic(&event_loop, |_| panic!("oops"));
+
+        assert!(event_loop.exited.get());
+        assert!(matches!(
+            wrapper.return_result,
+            Err(crate::Error::AppPanicked(message)) if message == "oops"
+        ));
+    }
+
+    #[test]
+    fn outdated_repaint_requests_are_ignored() {
+        let event_loop = FakeEventLoop::default();
+        let mut wrapper = wrapper();
+        for _ in 0..3 {
+            let _ = wrapper.winit_app.egui_ctx.run(Default::default(), |_| {});
+        }
+        let current_pass_nr = wrapper
+            .winit_app
+            .egui_ctx
+            .cumulative_pass_nr_for(ViewportId::ROOT);
+
+        let request = |cumulative_pass_nr| UserEvent::RequestRepaint {
+            viewport_id: ViewportId::ROOT,
+            when: Instant::now(),
+            cumulative_pass_nr,
+        };
+
+        wrapper.handle_user_event(&event_loop, request(current_pass_nr - 2));
+        assert!(redraws(&wrapper).is_empty());
+
+        wrapper.handle_user_event(&event_loop, request(current_pass_nr - 1));
+        assert_eq!(redraws(&wrapper), [ROOT_WINDOW]);
+
+        wrapper.handle_user_event(&event_loop, request(current_pass_nr));
+        assert_eq!(redraws(&wrapper), [ROOT_WINDOW, ROOT_WINDOW]);
+    }
+}
@@ -0,0 +1,340 @@
+//! Compares two event loop traces recorded with `EFRAME_EVENT_TRACE`, e.g. from `compare.sh`.
+//!
+//! Timestamps and window ids differ between runs, so entries are compared by
+//! kind, detail and the order in which windows first appeared.
+//! Runs of identical entries (e.g. lots of `CursorMoved`) are collapsed into one.
+
+use std::{collections::HashMap, path::Path, process::ExitCode};
+
+/// How far ahead to look for a matching entry after a divergence.
+const DEFAULT_LOOKAHEAD: usize = 64;
+
+const USAGE: &str =
+    "Usage: trace_diff <a.jsonl> <b.jsonl> [--ignore <kind>]... [--lookahead <n>] [--no-collapse]";
+
+struct Entry {
+    /// 1-based line in the trace file.
+    line: usize,
+    t: f64,
+    thread: String,
+    key: String,
+    /// Number of identical entries collapsed into this one.
+    count: usize,
+}
+
+struct Args {
+    a: String,
+    b: String,
+    ignore: Vec<String>,
+    lookahead: usize,
+    collapse: bool,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let (a, b) = match (load(&args.a, &args), load(&args.b, &args)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(err), _) | (_, Err(err)) => {
+            eprintln!("{err}");
+            return ExitCode::from(2);
+        }
+    };
+
+    println!(
+        "a: {} ({} entries, threads: {})",
+        args.a,
+        a.len(),
+        threads(&a)
+    );
+    println!(
+        "b: {} ({} entries, threads: {})",
+        args.b,
+        b.len(),
+        threads(&b)
+    );
+
+    let divergences = diff(&a, &b, args.lookahead);
+    if divergences == 0 {
+        println!("No divergences");
+        ExitCode::SUCCESS
+    } else {
+        println!("{divergences} divergence(s)");
+        ExitCode::FAILURE
+    }
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let mut paths = Vec::new();
+    let mut ignore = Vec::new();
+    let mut lookahead = DEFAULT_LOOKAHEAD;
+    let mut collapse = true;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ignore" => ignore.push(args.next().ok_or("--ignore needs a kind")?),
+            "--lookahead" => {
+                lookahead = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("--lookahead needs a number")?;
+            }
+            "--no-collapse" => collapse = false,
+            _ => paths.push(arg),
+        }
+    }
+
+    let [a, b] = <[String; 2]>::try_from(paths).map_err(|_| "Expected two trace files")?;
+    Ok(Args {
+        a,
+        b,
+        ignore,
+        lookahead,
+        collapse,
+    })
+}
+
+fn load(path: &str, args: &Args) -> Result<Vec<Entry>, String> {
+    let text = std::fs::read_to_string(Path::new(path))
+        .map_err(|err| format!("Failed to read {path}: {err}"))?;
+    parse(path, &text, args)
+}
+
+fn parse(path: &str, text: &str, args: &Args) -> Result<Vec<Entry>, String> {
+    // Window ids are assigned by the OS, so name them by order of appearance instead.
+    let mut windows: HashMap<String, usize> = HashMap::new();
+    let mut entries: Vec<Entry> = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: serde_json::Value = serde_json::from_str(line)
+            .map_err(|err| format!("{path}:{}: invalid JSON: {err}", i + 1))?;
+        let field = |name: &str| value[name].as_str().unwrap_or_default().to_owned();
+
+        let kind = field("kind");
+        if args.ignore.contains(&kind) {
+            continue;
+        }
+
+        let mut key = kind;
+        let detail = field("detail");
+        if !detail.is_empty() {
+            key = format!("{key} {detail}");
+        }
+        if let Some(window) = value["window"].as_str() {
+            let next = windows.len();
+            let n = *windows.entry(window.to_owned()).or_insert(next);
+            key = format!("{key} window#{n}");
+        }
+
+        if args.collapse
+            && let Some(last) = entries.last_mut()
+            && last.key == key
+        {
+            last.count += 1;
+            continue;
+        }
+
+        entries.push(Entry {
+            line: i + 1,
+            t: value["t"].as_f64().unwrap_or_default(),
+            thread: field("thread"),
+            key,
+            count: 1,
+        });
+    }
+
+    Ok(entries)
+}
+
+fn threads(entries: &[Entry]) -> String {
+    let mut threads: Vec<&str> = entries.iter().map(|e| e.thread.as_str()).collect();
+    threads.sort_unstable();
+    threads.dedup();
+    threads.join(", ")
+}
+
+/// Walks both traces in lockstep, printing every stretch where they differ.
+///
+/// After a divergence, the closest pair of matching entries within `lookahead` is used to resync.
+/// Returns the number of divergences.
+fn diff(a: &[Entry], b: &[Entry], lookahead: usize) -> usize {
+    let (mut i, mut j) = (0, 0);
+    let mut divergences = 0;
+
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i].key == b[j].key {
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        let (skip_a, skip_b) = resync(&a[i..], &b[j..], lookahead);
+        divergences += 1;
+        print_divergence(&a[i..i + skip_a], &b[j..j + skip_b], a.get(i), b.get(j));
+        i += skip_a;
+        j += skip_b;
+    }
+
+    divergences
+}
+
+/// How many entries to skip in each trace to get back in sync, or the rest of both if that's not possible.
+fn resync(a: &[Entry], b: &[Entry], lookahead: usize) -> (usize, usize) {
+    let max_a = a.len().min(lookahead);
+    let max_b = b.len().min(lookahead);
+    for total in 1..=max_a + max_b {
+        for skip_a in total.saturating_sub(max_b)..=total.min(max_a) {
+            let skip_b = total - skip_a;
+            if let (Some(a), Some(b)) = (a.get(skip_a), b.get(skip_b))
+                && a.key == b.key
+            {
+                return (skip_a, skip_b);
+            }
+        }
+    }
+    (a.len(), b.len())
+}
+
+fn print_divergence(
+    only_a: &[Entry],
+    only_b: &[Entry],
+    at_a: Option<&Entry>,
+    at_b: Option<&Entry>,
+) {
+    let location = |entry: Option<&Entry>| {
+        entry.map_or("end".to_owned(), |e| {
+            format!("line {} ({:.3}s, {})", e.line, e.t, e.thread)
+        })
+    };
+    println!();
+    println!("@@ a: {} | b: {}", location(at_a), location(at_b));
+    for entry in only_a {
+        println!("- {}", describe(entry));
+    }
+    for entry in only_b {
+        println!("+ {}", describe(entry));
+    }
+}
+
+fn describe(entry: &Entry) -> String {
+    if entry.count > 1 {
+        format!("{} (x{})", entry.key, entry.count)
+    } else {
+        entry.key.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(ignore: &[&str], collapse: bool) -> Args {
+        Args {
+            a: "a.jsonl".to_owned(),
+            b: "b.jsonl".to_owned(),
+            ignore: ignore.iter().map(|kind| (*kind).to_owned()).collect(),
+            lookahead: DEFAULT_LOOKAHEAD,
+            collapse,
+        }
+    }
+
+    fn entries(keys: &[&str]) -> Vec<Entry> {
+        keys.iter()
+            .enumerate()
+            .map(|(i, key)| Entry {
+                line: i + 1,
+                t: 0.0,
+                thread: "main".to_owned(),
+                key: (*key).to_owned(),
+                count: 1,
+            })
+            .collect()
+    }
+
+    fn keys(entries: &[Entry]) -> Vec<(&str, usize)> {
+        entries.iter().map(|e| (e.key.as_str(), e.count)).collect()
+    }
+
+    const TRACE: &str = r#"
+{"t":0.1,"thread":"main","kind":"resumed","detail":""}
+{"t":0.2,"thread":"main","kind":"window_event","detail":"CursorMoved","window":"WindowId(7)"}
+{"t":0.3,"thread":"main","kind":"window_event","detail":"CursorMoved","window":"WindowId(7)"}
+{"t":0.4,"thread":"main","kind":"window_event","detail":"RedrawRequested","window":"WindowId(9)"}
+{"t":0.5,"thread":"main","kind":"control_flow","detail":"Wait"}
+"#;
+
+    #[test]
+    fn parse_numbers_windows_and_collapses_runs() {
+        let entries = parse("a.jsonl", TRACE, &args(&[], true)).unwrap();
+        assert_eq!(
+            keys(&entries),
+            [
+                ("resumed", 1),
+                ("window_event CursorMoved window#0", 2),
+                ("window_event RedrawRequested window#1", 1),
+                ("control_flow Wait", 1),
+            ]
+        );
+        assert_eq!(entries[1].line, 3);
+    }
+
+    #[test]
+    fn parse_ignores_kinds_and_can_keep_runs() {
+        let entries = parse("a.jsonl", TRACE, &args(&["window_event"], true)).unwrap();
+        assert_eq!(keys(&entries), [("resumed", 1), ("control_flow Wait", 1)]);
+
+        let entries = parse("a.jsonl", TRACE, &args(&[], false)).unwrap();
+        assert_eq!(entries.len(), 5);
+    }
+
+    #[test]
+    fn parse_reports_the_bad_line() {
+        let err = parse("a.jsonl", "{}\nnot json", &args(&[], true))
+            .err()
+            .unwrap();
+        assert!(err.starts_with("a.jsonl:2:"), "{err}");
+    }
+
+    #[test]
+    fn identical_traces_have_no_divergences() {
+        let a = entries(&["x", "y", "z"]);
+        assert_eq!(diff(&a, &entries(&["x", "y", "z"]), DEFAULT_LOOKAHEAD), 0);
+    }
+
+    #[test]
+    fn resync_finds_the_closest_match() {
+        let a = entries(&["extra", "y", "z"]);
+        let b = entries(&["y", "z"]);
+        assert_eq!(resync(&a, &b, DEFAULT_LOOKAHEAD), (1, 0));
+        assert_eq!(resync(&b, &a, DEFAULT_LOOKAHEAD), (0, 1));
+
+        let a = entries(&["p", "q", "z"]);
+        let b = entries(&["r", "z"]);
+        assert_eq!(resync(&a, &b, DEFAULT_LOOKAHEAD), (2, 1));
+    }
+
+    #[test]
+    fn resync_gives_up_beyond_the_lookahead() {
+        let a = entries(&["p", "q", "r", "z"]);
+        let b = entries(&["z"]);
+        assert_eq!(resync(&a, &b, 2), (4, 1));
+    }
+
+    #[test]
+    fn diff_counts_each_divergent_stretch() {
+        let a = entries(&["x", "extra", "y", "z", "tail"]);
+        let b = entries(&["x", "y", "other", "z"]);
+        // "extra", then "other", then "tail".
+        assert_eq!(diff(&a, &b, DEFAULT_LOOKAHEAD), 3);
+    }
+}